@@ -0,0 +1,189 @@
+//! Canonicalization of locale IDs.
+//!
+//! This module provides a function `locale_id` that maps an `*.lproj` file name to a canonical locale ID, and a
+//! similar function `canonical_locale_id` for other locale names, e.g. Windows culture names and the
+//! `CFBundleDevelopmentRegion` in `Info.plist`. Aliases to the same locale, e.g. `English.lproj`, `en.lproj`,
+//! `en-US.lproj` and `en_Latn_US.lproj` are all mapped to the same output, `en_US`.
+//!
+//! This combination simplifies searching, because the user will only need to look for the `en_US` key, instead of all
+//! the variants.
+//!
+//! Legacy names like `English` are first replaced by their language tags, which are then canonicalized as described in
+//! the `locales` module.
+//!
+//! Additional aliases can be added by `add_aliases`. These map a locale name, either as written or after
+//! canonicalization, directly to the final locale ID.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use locales::{Locale, LANGUAGE_ALIASES, LIKELY_SUBTAGS, LIKELY_REGIONS};
+
+lazy_static! {
+    /// User-defined aliases, which take precedence over everything else.
+    static ref USER_ALIASES: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+
+    /// Legacy names of locales used by Apple and Microsoft, mapped to the corresponding language tags.
+    static ref LEGACY_NAMES: HashMap<&'static str, &'static str> = hashmap!{
+        "Afrikaans" => "af", "Albanian" => "sq", "Amharic" => "am", "Arabic" => "ar", "Armenian" => "hy",
+        "Assamese" => "as", "Aymara" => "ay", "Azerbaijani" => "az",
+        "Basque" => "eu", "Bengali" => "bn", "Breton" => "br", "Bulgarian" => "bg", "Burmese" => "my",
+        "Byelorussian" => "be",
+        "Catalan" => "ca", "Croatian" => "hr", "Czech" => "cs",
+        "Danish" => "da", "Dutch" => "nl", "Dzongkha" => "dz",
+        "English" => "en", "Esperanto" => "eo", "Estonian" => "et",
+        "Faroese" => "fo", "Farsi" => "fa", "Finnish" => "fi", "French" => "fr",
+        "Galician" => "gl", "Georgian" => "ka", "German" => "de", "Greek" => "el", "Greenlandic" => "kl",
+        "Guarani" => "gn", "Gujarati" => "gu",
+        "Hebrew" => "he", "Hindi" => "hi", "Hungarian" => "hu",
+        "Icelandic" => "is", "Indonesian" => "id", "Inuktitut" => "iu", "Irish" => "ga", "Italian" => "it",
+        "Japanese" => "ja", "Javanese" => "jv",
+        "Kannada" => "kn", "Kashmiri" => "ks", "Kazakh" => "kk", "Khmer" => "km", "Kinyarwanda" => "rw",
+        "Kirghiz" => "ky", "Korean" => "ko", "Kurdish" => "ku",
+        "Lao" => "lo", "Latin" => "la", "Latvian" => "lv", "Lithuanian" => "lt",
+        "Macedonian" => "mk", "Malagasy" => "mg", "Malay" => "ms", "Malayalam" => "ml", "Maltese" => "mt",
+        "Manx" => "gv", "Marathi" => "mr", "Moldavian" => "ro-MD", "Mongolian" => "mn",
+        "Nepali" => "ne", "Norwegian" => "nb", "Nyanja" => "ny", "Nynorsk" => "nn",
+        "Oriya" => "or", "Oromo" => "om",
+        "Pashto" => "ps", "Polish" => "pl", "Portuguese" => "pt", "Punjabi" => "pa",
+        "Quechua" => "qu",
+        "Romanian" => "ro", "Rundi" => "rn", "Russian" => "ru",
+        "Sami" => "se", "Sanskrit" => "sa", "Scottish" => "gd", "Serbian" => "sr", "Sindhi" => "sd",
+        "Sinhalese" => "si", "Slovak" => "sk", "Slovenian" => "sl", "Somali" => "so", "Spanish" => "es",
+        "Sundanese" => "su", "Swahili" => "sw", "Swedish" => "sv",
+        "Tagalog" => "fil", "Tajiki" => "tg", "Tamil" => "ta", "Tatar" => "tt", "Telugu" => "te", "Thai" => "th",
+        "Tibetan" => "bo", "Tigrinya" => "ti", "Tongan" => "to", "Turkish" => "tr", "Turkmen" => "tk",
+        "Uighur" => "ug", "Ukrainian" => "uk", "Urdu" => "ur", "Uzbek" => "uz",
+        "Vietnamese" => "vi",
+        "Welsh" => "cy",
+        "Yiddish" => "yi",
+
+        "zh-CHS" => "zh-Hans", // Legacy .NET culture names.
+        "zh-CHT" => "zh-Hant",
+    };
+}
+
+/// Checks whether a lowercase language subtag is known, either with likely subtags (e.g. `de`) or as a deprecated alias
+/// (e.g. `iw`).
+pub fn is_known_language(language: &str) -> bool {
+    LIKELY_SUBTAGS.contains_key(language) || LANGUAGE_ALIASES.contains_key(language)
+}
+
+/// Gets the locale ID of a locale name like `"de-DE"`, `"de"` or `"German"`. The locale ID is always of the form like
+/// `"en_US"` or `"zh_Hans_HK"`, unless overridden by a user-defined alias. Names which are not language tags of a known
+/// language (e.g. `"Base"` or `"Default"`) are returned unchanged.
+pub fn canonical_locale_id(locale_name: &str) -> String {
+    resolve_locale_id(&USER_ALIASES.read().unwrap(), locale_name)
+}
+
+/// Implementation of `canonical_locale_id` with the given user-defined aliases.
+fn resolve_locale_id(user_aliases: &HashMap<String, String>, locale_name: &str) -> String {
+    if let Some(id) = user_aliases.get(locale_name) {
+        return id.clone();
+    }
+
+    let tag = LEGACY_NAMES.get(locale_name).unwrap_or(&locale_name);
+    let id = match Locale::parse(tag) {
+        Some(ref locale) if !is_known_language(&locale.language) => return locale_name.to_owned(),
+        Some(locale) => locale.canonicalize().to_string(),
+        None => return locale_name.to_owned(),
+    };
+    user_aliases.get(&id).cloned().unwrap_or(id)
+}
+
+/// Adds user-defined aliases, overriding the built-in ones.
+pub fn add_aliases<I: IntoIterator<Item=(String, String)>>(aliases: I) {
+    USER_ALIASES.write().unwrap().extend(aliases);
+}
+
+/// Lists the locale IDs of all known names, including the legacy names, the languages with likely subtags and the
+/// user-defined aliases.
+pub fn effective_aliases() -> BTreeMap<String, String> {
+    alias_table(&USER_ALIASES.read().unwrap())
+}
+
+/// Implementation of `effective_aliases` with the given user-defined aliases.
+fn alias_table(user_aliases: &HashMap<String, String>) -> BTreeMap<String, String> {
+    let builtin_names = LEGACY_NAMES.keys()
+        .chain(LANGUAGE_ALIASES.keys())
+        .chain(LIKELY_SUBTAGS.keys())
+        .chain(LIKELY_REGIONS.keys())
+        .map(|&name| name.to_owned());
+    builtin_names.chain(user_aliases.keys().cloned()).map(|name| {
+        let id = resolve_locale_id(user_aliases, &name);
+        (name, id)
+    }).collect()
+}
+
+#[test]
+fn test_canonical_locale_id() {
+    assert_eq!(canonical_locale_id("de-DE"), "de_DE");
+    assert_eq!(canonical_locale_id("de"), "de_DE");
+    assert_eq!(canonical_locale_id("English"), "en_US");
+    assert_eq!(canonical_locale_id("en_Latn_US"), "en_US");
+    assert_eq!(canonical_locale_id("zh-Hant"), "zh_TW");
+    assert_eq!(canonical_locale_id("zh-Hans"), "zh_CN");
+    assert_eq!(canonical_locale_id("zh_Hant_HK"), "zh_HK");
+    assert_eq!(canonical_locale_id("zh-HK"), "zh_HK");
+    assert_eq!(canonical_locale_id("zh-Hans-HK"), "zh_Hans_HK");
+    assert_eq!(canonical_locale_id("zh-CHS"), "zh_CN");
+    assert_eq!(canonical_locale_id("sr"), "sr_RS");
+    assert_eq!(canonical_locale_id("sr-Latn"), "sr_Latn_RS");
+    assert_eq!(canonical_locale_id("sr_CS"), "sr_RS");
+    assert_eq!(canonical_locale_id("es-419"), "es_419");
+    assert_eq!(canonical_locale_id("iw"), "he_IL");
+    assert_eq!(canonical_locale_id("no"), "nb_NO");
+    assert_eq!(canonical_locale_id("ca-ES-valencia"), "ca_ES_valencia");
+    assert_eq!(canonical_locale_id("Base"), "Base");
+    assert_eq!(canonical_locale_id("Default"), "Default");
+    assert_eq!(canonical_locale_id("xx-YY"), "xx-YY");
+}
+
+#[test]
+fn test_user_aliases() {
+    let user_aliases = hashmap! {
+        "yue".to_owned() => "zh_yue".to_owned(),
+        "cy_GB".to_owned() => "cy".to_owned(),
+    };
+    assert_eq!(resolve_locale_id(&user_aliases, "yue"), "zh_yue");
+    assert_eq!(resolve_locale_id(&user_aliases, "yue-HK"), "yue_HK");
+    assert_eq!(resolve_locale_id(&user_aliases, "Welsh"), "cy");
+    assert_eq!(resolve_locale_id(&HashMap::new(), "Welsh"), "cy_GB");
+    assert_eq!(alias_table(&user_aliases)["yue"], "zh_yue");
+    assert_eq!(alias_table(&user_aliases)["cy_GB"], "cy");
+}
+
+/// Gets the locale ID of an `*.lproj` folder. The locale ID is of the same form as `canonical_locale_id`.
+pub fn locale_id(lproj_name: &str) -> String {
+    debug_assert!(lproj_name.ends_with(".lproj"));
+    canonical_locale_id(&lproj_name[.. lproj_name.len()-6])
+}
+
+#[test]
+fn test_locale_id() {
+    assert_eq!(locale_id("en.lproj"), "en_US");
+    assert_eq!(locale_id("French.lproj"), "fr_FR");
+    assert_eq!(locale_id("es_419.lproj"), "es_419");
+    assert_eq!(locale_id("zh-Hans.lproj"), "zh_CN");
+    assert_eq!(locale_id("unknown.lproj"), "unknown");
+    assert_eq!(locale_id("Default.lproj"), "Default");
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
\ No newline at end of file
@@ -1,6 +1,8 @@
 #[macro_use] extern crate structopt_derive;
 #[macro_use] extern crate iron;
 #[macro_use] extern crate serde_json;
+#[macro_use] extern crate lazy_static;
+#[macro_use] extern crate maplit;
 extern crate mount;
 extern crate structopt;
 extern crate hyper;
@@ -12,6 +14,8 @@ extern crate url;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::mem::replace;
+use std::cmp::{min, max, Ordering};
+use std::iter::once;
 use std::path::Path;
 use iron::prelude::*;
 use iron::headers::ContentType;
@@ -23,11 +27,32 @@ use hyper::client::{Body, RequestBuilder};
 use hyper::header::{Authorization, Basic};
 use hyper::method::Method;
 use serde::Serialize;
-use serde_json::{Value, from_reader, to_vec};
+use serde_json::{Value, Map, from_reader, from_str, to_vec};
 use params::Params;
 use params::Value::String as PVString;
 use staticfile::Static;
-use url::percent_encoding::percent_decode;
+use url::percent_encoding::{percent_decode, utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+
+#[path="../locales.rs"]
+mod locales;
+
+#[path="../locale_info.rs"]
+mod locale_info;
+
+#[path="../diff.rs"]
+mod diff;
+
+#[path="../coverage.rs"]
+mod coverage;
+
+#[path="../scroll.rs"]
+mod scroll;
+
+use locales::is_locale_field;
+use locale_info::{fallback_locale_ids, locale_info};
+use diff::{diff, diff_text, Snapshot, TextEdit};
+use coverage::Coverage;
+use scroll::scroll;
 
 #[derive(StructOpt)]
 struct Options {
@@ -62,90 +87,687 @@ impl Searcher {
         req
     }
 
-    fn list_languages(&self) -> IronResult<Vec<String>> {
+    /// Lists the locale fields in the mapping of the index.
+    fn locale_fields(&self) -> IronResult<Vec<String>> {
         let path = format!("/{}/_mappings/{}", self.index, self.type_);
         let resp = itry!(self.request(Method::Get, &path).send());
         let content: Value = itry!(from_reader(resp));
         let properties = content[&self.index]["mappings"][&self.type_]["properties"].as_object();
-        Ok(properties.map(|p| p.keys().filter(|s| s.contains('_')).cloned().collect()).unwrap_or_else(Vec::new))
+        let locales = properties.into_iter().flat_map(|p| p.iter()).filter(|&(name, mapping)| {
+            mapping["type"] == "text" && is_locale_field(name)
+        });
+        Ok(locales.map(|(name, _)| name.clone()).collect())
     }
 
-    fn search<'a, I>(&self, source: &str, targets: I, keyword: &str) -> IronResult<Vec<Value>>
-        where I: Iterator<Item=&'a str>
-    {
-        let path = format!("/{}/{}/_search", self.index, self.type_);
-        let query = construct_search_query(source, targets, keyword);
-        let body = to_vec(&query).unwrap();
-        let resp = itry!(self.request(Method::Post, &path)
+    fn list_languages(&self) -> IronResult<Vec<Value>> {
+        Ok(self.locale_fields()?.iter().map(|l| locale_metadata(l)).collect())
+    }
+
+    /// Posts a JSON request to Elasticsearch.
+    fn post_json(&self, path: &str, request: &Value) -> IronResult<Value> {
+        let body = to_vec(request).unwrap();
+        let resp = itry!(self.request(Method::Post, path)
             .header(ContentType::json())
             .body(Body::BufBody(&body, body.len()))
             .send());
-        let content = itry!(from_reader(resp));
-        Ok(parse_search_result(content))
+        Ok(itry!(from_reader(resp)))
     }
-}
 
-fn construct_search_query<'a, I>(source: &str, targets: I, keyword: &str) -> Value
-        where I: Iterator<Item=&'a str>
-{
-    let mut aggs = json!({"entry": {"top_hits": {"size": 1}}});
-    for target in targets {
-        aggs = json!({
-            target: {
-                "terms": {
-                    "field": format!("{}.keyword", target),
-                    "missing": "",
+    /// Runs a search request against the index.
+    fn post_search(&self, query: &Value) -> IronResult<Value> {
+        self.post_json(&format!("/{}/{}/_search", self.index, self.type_), query)
+    }
+
+    /// Reads every document matching the query using the scroll API, and passes the `_source` of each to `f`.
+    fn scan<F: FnMut(Value)>(&self, query: Value, f: F) -> IronResult<()> {
+        scroll(&self.index, &self.type_, query, |path, request| self.post_json(path, request), f)
+    }
+
+    /// Reads all translations of a build.
+    fn read_snapshot(&self, build: &str) -> IronResult<Snapshot> {
+        let mut snapshot = Snapshot::default();
+        self.scan(json!({"term": {"BUILD.keyword": build}}), |document| snapshot.add_document(&document))?;
+        Ok(snapshot)
+    }
+
+    fn search(&self, group_by: &[&str], query: &KeywordQuery, filters: &SearchFilters, page: &Page,
+        ratio_order: Option<&str>) -> IronResult<SearchResult>
+    {
+        let content = self.post_search(&construct_search_query(group_by, query, filters, page, ratio_order))?;
+        Ok(if is_ranked(query, ratio_order) {
+            parse_ranked_search_result(content, page)
+        } else {
+            parse_search_result(content, page.size)
+        })
+    }
+
+    /// Gets a document by its ID. Returns `None` if it does not exist.
+    fn get_entry(&self, id: &str) -> IronResult<Option<Value>> {
+        let path = format!("/{}/{}/{}", self.index, self.type_, utf8_percent_encode(id, PATH_SEGMENT_ENCODE_SET));
+        let resp = itry!(self.request(Method::Get, &path).send());
+        let content: Value = itry!(from_reader(resp));
+        Ok(if content["found"].as_bool() == Some(true) { Some(content) } else { None })
+    }
+
+    /// Finds the documents of a key in a `*.strings` file of a bundle, one for each build.
+    fn find_entries(&self, bundle: &str, file: &str, key: &str) -> IronResult<Vec<Value>> {
+        let mut content = self.post_search(&json!({
+            "query": {
+                "bool": {
+                    "filter": [
+                        bundle_filter(bundle),
+                        {"term": {"FILE.keyword": file}},
+                        {"term": {"KEY.keyword": key}},
+                    ],
                 },
-                "aggregations": aggs,
             },
-        });
+            "size": MAX_PAGE_SIZE,
+        }))?;
+        Ok(take_hits(&mut content))
     }
 
+    /// Reads the coverage of all bundles of a build, or of a single bundle.
+    fn read_coverage(&self, build: Option<&str>, bundle: Option<&str>) -> IronResult<Coverage> {
+        let filters = browse_filters(build, bundle);
+        let mut coverage = Coverage::default();
+        self.scan(json!({"bool": {"filter": filters}}), |document| coverage.add_document(&document))?;
+        Ok(coverage)
+    }
+
+    /// Lists the bundles with their number of documents.
+    fn list_bundles(&self, build: Option<&str>) -> IronResult<Vec<Value>> {
+        self.count_terms(json!({"script": BUNDLE_KEY_SCRIPT}), &browse_filters(build, None))
+    }
+
+    /// Lists the files of a bundle with their number of keys.
+    fn list_files(&self, bundle: &str, build: Option<&str>) -> IronResult<Vec<Value>> {
+        self.count_terms(json!({"field": "FILE.keyword"}), &browse_filters(build, Some(bundle)))
+    }
+
+    /// Lists all documents of a file in a bundle, sorted by the key.
+    fn list_file_entries(&self, bundle: &str, file: &str, build: Option<&str>) -> IronResult<Vec<Value>> {
+        let mut filters = browse_filters(build, Some(bundle));
+        filters.push(json!({"term": {"FILE.keyword": file}}));
+        let mut content = self.post_search(&json!({
+            "query": {"bool": {"filter": filters}},
+            "sort": ["KEY.keyword"],
+            "size": MAX_BROWSE_SIZE,
+        }))?;
+        Ok(take_hits(&mut content))
+    }
+
+    /// Counts the documents of each distinct value of a keyword field or script, e.g. `[{"name": "…", "count": 12}]`.
+    /// The `terms` source is `{"field": …}` or `{"script": …}`.
+    fn count_terms(&self, mut terms: Value, filters: &[Value]) -> IronResult<Vec<Value>> {
+        terms["size"] = json!(MAX_BROWSE_SIZE);
+        terms["order"] = json!({"_key": "asc"});
+        let content = self.post_search(&json!({
+            "query": {"bool": {"filter": filters}},
+            "size": 0,
+            "aggregations": {"terms": {"terms": terms}},
+        }))?;
+        let buckets = content["aggregations"]["terms"]["buckets"].as_array();
+        Ok(buckets.into_iter().flat_map(|b| b.iter()).map(|bucket| {
+            json!({"name": bucket["key"], "count": bucket["doc_count"]})
+        }).collect())
+    }
+}
+
+/// Maximum number of bundles, files or keys listed by the browser.
+const MAX_BROWSE_SIZE: u32 = 10000;
+
+/// Identifies the bundle of a document by its `BUNDLE_ID`, or the `BUNDLE` path if missing, like the coverage and diff
+/// reports.
+const BUNDLE_KEY_SCRIPT: &str = "doc['BUNDLE_ID.keyword'].size() == 0 ? \
+    doc['BUNDLE.keyword'].value : doc['BUNDLE_ID.keyword'].value";
+
+/// Matches the documents of a bundle given by its `BUNDLE_ID` or its `BUNDLE` path.
+fn bundle_filter(bundle: &str) -> Value {
     json!({
-        "query": {"match": {source: keyword}},
+        "bool": {
+            "should": [
+                {"term": {"BUNDLE_ID.keyword": bundle}},
+                {"term": {"BUNDLE.keyword": bundle}},
+            ],
+        },
+    })
+}
+
+/// Constructs the exact-match filters of the browser.
+fn browse_filters(build: Option<&str>, bundle: Option<&str>) -> Vec<Value> {
+    let mut filters = Vec::new();
+    if let Some(build) = build {
+        filters.push(json!({"term": {"BUILD.keyword": build}}));
+    }
+    if let Some(bundle) = bundle {
+        filters.push(bundle_filter(bundle));
+    }
+    filters
+}
+
+/// Takes the documents out of a search response.
+fn take_hits(content: &mut Value) -> Vec<Value> {
+    content["hits"]["hits"].as_array_mut().map_or_else(Vec::new, |hits| replace(hits, Vec::new()))
+}
+
+/// Describes a locale for the language selectors, e.g. `{"id": "he_IL", "name": "Hebrew (Israel)", "dir": "rtl", …}`.
+fn locale_metadata(id: &str) -> Value {
+    let info = locale_info(id);
+    json!({
+        "id": info.id,
+        "name": info.name,
+        "nativeName": info.native_name,
+        "script": info.script,
+        "dir": if info.rtl { "rtl" } else { "ltr" },
+        "plurals": info.plural_categories,
+    })
+}
+
+/// A page of grouped search results.
+struct SearchResult {
+    /// One document of each distinct group of translations.
+    results: Vec<Value>,
+    /// Number of documents matching the query.
+    total: Value,
+    /// Number of groups of the matching documents. This is approximate above `GROUP_COUNT_PRECISION` groups.
+    groups: u64,
+    /// Whether some groups are never listed, because a ranked search only considers the `MAX_RANKED_GROUPS` best ones.
+    truncated: bool,
+    /// Cursor of the next page, to be passed as the `page` parameter. `None` on the last page.
+    next: Option<String>,
+}
+
+/// The range of groups to return.
+struct Page {
+    /// Maximum number of groups in a page.
+    size: u32,
+    /// The `after_key` of the previous page, if any. For ranked searches, this is the number of groups already
+    /// returned instead.
+    after: Option<Value>,
+}
+
+/// Default number of groups in a page.
+const DEFAULT_PAGE_SIZE: u32 = 100;
+/// Maximum number of groups in a page.
+const MAX_PAGE_SIZE: u32 = 1000;
+
+impl Page {
+    /// Reads the `size` and `page` parameters. Returns `None` if they are malformed.
+    fn from_params(params: &params::Map) -> Option<Page> {
+        let size = match params.get("size") {
+            Some(&PVString(ref size)) => size.parse().ok()?,
+            _ => DEFAULT_PAGE_SIZE,
+        };
+        let after = match params.get("page") {
+            Some(&PVString(ref page)) if !page.is_empty() => Some(from_str::<Value>(page).ok()?),
+            _ => None,
+        };
+        Some(Page {
+            size: min(max(size, 1), MAX_PAGE_SIZE),
+            after: after,
+        })
+    }
+}
+
+/// How the keyword is matched against the translations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SearchMode {
+    /// Full-text match of any of the words.
+    Match,
+    /// The whole translation equals the keyword.
+    Exact,
+    /// The translation contains the words in the same order.
+    Phrase,
+    /// Full-text match tolerating typos.
+    Fuzzy,
+    /// The translation starts with the keyword.
+    Prefix,
+    /// The whole translation matches the keyword as a regular expression (Lucene syntax).
+    Regex,
+}
+
+impl SearchMode {
+    fn from_param(mode: &str) -> Option<SearchMode> {
+        Some(match mode {
+            "" | "match" => SearchMode::Match,
+            "exact" => SearchMode::Exact,
+            "phrase" => SearchMode::Phrase,
+            "fuzzy" => SearchMode::Fuzzy,
+            "prefix" => SearchMode::Prefix,
+            "regex" => SearchMode::Regex,
+            _ => return None,
+        })
+    }
+
+    /// Constructs the query matching `keyword` in any of the `fields`.
+    fn to_query(self, fields: &[String], keyword: &str) -> Value {
+        let kind = match self {
+            SearchMode::Match => return json!({"multi_match": {"query": keyword, "fields": fields}}),
+            SearchMode::Phrase => return json!({"multi_match": {"query": keyword, "fields": fields, "type": "phrase"}}),
+            SearchMode::Fuzzy => {
+                return json!({"multi_match": {"query": keyword, "fields": fields, "fuzziness": "AUTO"}});
+            },
+            SearchMode::Exact => "term",
+            SearchMode::Prefix => "prefix",
+            SearchMode::Regex => "regexp",
+        };
+        let should = fields.iter().map(|field| {
+            let keyword_field = format!("{}.keyword", field);
+            json!({kind: {keyword_field: keyword}})
+        }).collect::<Vec<_>>();
+        json!({"bool": {"should": should, "minimum_should_match": 1}})
+    }
+}
+
+/// The keyword to look for, and where.
+struct KeywordQuery<'a> {
+    keyword: &'a str,
+    mode: SearchMode,
+    /// The locale fields to match the keyword against, e.g. `["de_DE"]` when reviewing a German translation.
+    locales: Vec<String>,
+}
+
+/// Optional restrictions on the documents to search.
+#[derive(Default)]
+struct SearchFilters {
+    /// Bundle path or name, e.g. `Preferences.framework`.
+    bundle: Option<String>,
+    /// File name, e.g. `Localizable.strings`.
+    file: Option<String>,
+    /// Key, e.g. `AWAY_LOCK_LABEL`.
+    key: Option<String>,
+    /// Build identifier, e.g. `14E304`.
+    build: Option<String>,
+    /// Minimum expansion ratio of the translations in `ratio_locale` over the source text, e.g. `1.5`.
+    min_ratio: Option<f64>,
+    /// The locale whose expansion ratio is filtered, usually the first target locale.
+    ratio_locale: Option<String>,
+}
+
+impl SearchFilters {
+    fn from_params(params: &params::Map) -> SearchFilters {
+        let get = |name: &str| match params.get(name) {
+            Some(&PVString(ref value)) if !value.is_empty() => Some(value.clone()),
+            _ => None,
+        };
+        SearchFilters {
+            bundle: get("bundle"),
+            file: get("file"),
+            key: get("key"),
+            build: get("build"),
+            min_ratio: get("min_ratio").and_then(|r| r.parse().ok()),
+            ratio_locale: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bundle.is_none() && self.file.is_none() && self.key.is_none() && self.build.is_none()
+            && self.min_ratio.is_none()
+    }
+
+    /// Converts the filters into the `filter` clauses of a bool query.
+    ///
+    /// The bundle, file and key are matched by prefix, or as a wildcard pattern if they contain `*` or `?`. A bundle
+    /// without `/` is matched against the last path components instead, e.g. `Preferences` matches
+    /// `/System/Library/PrivateFrameworks/Preferences.framework`.
+    fn to_clauses(&self) -> Vec<Value> {
+        let mut clauses = Vec::new();
+        if let Some(ref bundle) = self.bundle {
+            clauses.push(if bundle.contains('/') || is_wildcard(bundle) {
+                pattern_query("BUNDLE", bundle)
+            } else {
+                pattern_query("BUNDLE", &format!("*/{}*", bundle))
+            });
+        }
+        if let Some(ref file) = self.file {
+            clauses.push(pattern_query("FILE", file));
+        }
+        if let Some(ref key) = self.key {
+            clauses.push(pattern_query("KEY", key));
+        }
+        if let Some(ref build) = self.build {
+            clauses.push(json!({"term": {"BUILD.keyword": build}}));
+        }
+        if let (Some(min_ratio), Some(locale)) = (self.min_ratio, self.ratio_locale.as_ref()) {
+            clauses.push(json!({"range": {ratio_field(locale): {"gte": min_ratio}}}));
+        }
+        clauses
+    }
+}
+
+/// The field storing the expansion ratio of the translations in a locale, computed at index time.
+fn ratio_field(locale: &str) -> String {
+    format!("LENGTH.{}.ratio", locale)
+}
+
+/// Name of the composite aggregation source ordering the groups by expansion ratio. Locale IDs never start with an
+/// uppercase letter, so this does not clash with the sources of the locales.
+const RATIO_SOURCE: &str = "LENGTH_RATIO";
+
+fn is_wildcard(pattern: &str) -> bool {
+    pattern.contains(|c| c == '*' || c == '?')
+}
+
+/// Matches the keyword subfield of `field` by prefix, or by wildcard if the pattern contains `*` or `?`.
+fn pattern_query(field: &str, pattern: &str) -> Value {
+    let kind = if is_wildcard(pattern) { "wildcard" } else { "prefix" };
+    let keyword_field = format!("{}.keyword", field);
+    json!({kind: {keyword_field: pattern}})
+}
+
+/// Number of groups considered when ranking the results of a keyword search. Groups beyond these are not listed, and
+/// the result is marked as `truncated`.
+const MAX_RANKED_GROUPS: u32 = 1000;
+
+/// Number of groups below which the group count is exact. This is the maximum supported by the `cardinality`
+/// aggregation.
+const GROUP_COUNT_PRECISION: u32 = 40000;
+
+/// Computes the group of a document from the keyword subfields in `params.fields`, to rank the groups and to count
+/// them. Each value is prefixed by its length, and missing values are written as `-`, so different groups never share
+/// the same key.
+const GROUP_KEY_SCRIPT: &str = "String key = ''; for (def field : params.fields) { \
+    if (doc[field].size() == 0) { key += '-'; } \
+    else { String value = doc[field].value; key += value.length() + ':' + value; } \
+} return key;";
+
+/// Checks whether the results of a search are ranked by relevance. This is the case when searching for a keyword,
+/// unless sorted by expansion ratio; otherwise the groups are listed in the order of the translations.
+fn is_ranked(query: &KeywordQuery, ratio_order: Option<&str>) -> bool {
+    !query.keyword.is_empty() && ratio_order.is_none()
+}
+
+/// Constructs the search query, grouping the matched documents by the translations of the `group_by` locales so
+/// identical translations in different bundles are shown once.
+///
+/// Keyword searches are ranked: the `MAX_RANKED_GROUPS` groups with the best matching documents are fetched with a
+/// `terms` aggregation, then ordered by `score × count^0.25` in `parse_ranked_search_result`, where `score` is the
+/// best score in the group and `count` the number of documents. Every page fetches the same groups, so the ranking is
+/// the same across pages. Other searches page through a composite aggregation, where the first locale is the primary
+/// sort order, unless `ratio_order` gives a locale to list the translations expanding the most from the source text
+/// first. Both count the groups with a `cardinality` aggregation over the same key as the ranked search.
+fn construct_search_query(group_by: &[&str], query: &KeywordQuery, filters: &SearchFilters, page: &Page,
+    ratio_order: Option<&str>) -> Value
+{
+    let must = if query.keyword.is_empty() {
+        json!([])
+    } else {
+        json!([query.mode.to_query(&query.locales, query.keyword)])
+    };
+    let mut filter = filters.to_clauses();
+    // Documents without the first locale are not in any group.
+    filter.push(json!({"exists": {"field": format!("{}.keyword", group_by[0])}}));
+    let fields = group_by.iter().map(|locale| format!("{}.keyword", locale)).collect::<Vec<_>>();
+    let group_key = json!({"source": GROUP_KEY_SCRIPT, "params": {"fields": fields}});
+
+    let highlight_fields = query.locales.iter().map(|locale| {
+        (locale.clone(), json!({"number_of_fragments": 0}))
+    }).collect::<Map<_, _>>();
+    let entry = json!({
+        "top_hits": {
+            "size": 1,
+            "highlight": {
+                "fields": highlight_fields,
+                "pre_tags": [HIGHLIGHT_START],
+                "post_tags": [HIGHLIGHT_END],
+            },
+        },
+    });
+
+    let groups = if is_ranked(query, ratio_order) {
+        json!({
+            "terms": {
+                "script": group_key.clone(),
+                "size": MAX_RANKED_GROUPS,
+                "order": {"top_score": "desc"},
+            },
+            "aggregations": {
+                "top_score": {"max": {"script": {"source": "_score"}}},
+                "entry": entry,
+            },
+        })
+    } else {
+        json!({
+            "composite": composite_aggregation(group_by, page, ratio_order),
+            "aggregations": {"entry": entry},
+        })
+    };
+
+    json!({
+        "query": {
+            "bool": {
+                "must": must,
+                "filter": filter,
+            },
+        },
         "size": 0,
         "aggregations": {
-            source: {
+            "groups": groups,
+            "group_count": {"cardinality": {"script": group_key, "precision_threshold": GROUP_COUNT_PRECISION}},
+        },
+    })
+}
+
+/// Constructs the composite aggregation paging through the groups of an unranked search.
+fn composite_aggregation(group_by: &[&str], page: &Page, ratio_order: Option<&str>) -> Value {
+    let ratio_source = ratio_order.map(|locale| {
+        json!({RATIO_SOURCE: {"terms": {"field": ratio_field(locale), "order": "desc", "missing_bucket": true}}})
+    });
+    let sources = ratio_source.into_iter().chain(group_by.iter().enumerate().map(|(i, &locale)| {
+        json!({
+            locale: {
                 "terms": {
-                    "field": format!("{}.keyword", source),
-                    "collect_mode": "breadth_first",
-                    "size": 100,
+                    "field": format!("{}.keyword", locale),
+                    "missing_bucket": i != 0,
                 },
-                "aggregations": aggs,
             },
-        },
-    })
+        })
+    })).collect::<Vec<_>>();
+
+    let mut composite = json!({"size": page.size, "sources": sources});
+    if let Some(ref after) = page.after {
+        composite["after"] = after.clone();
+    }
+    composite
 }
 
-fn parse_search_result(mut content: Value) -> Vec<Value> {
-    let mut result = Vec::new();
+/// Marks the start of a highlighted match. A private-use character is used instead of an HTML tag, so the translation
+/// does not need to be HTML-escaped.
+const HIGHLIGHT_START: &str = "\u{e000}";
+/// Marks the end of a highlighted match.
+const HIGHLIGHT_END: &str = "\u{e001}";
 
-    macro_rules! try_opt {
-        ($e:expr) => {
-            match $e {
-                Some(e) => e,
-                None => return result,
+fn parse_search_result(mut content: Value, page_size: u32) -> SearchResult {
+    let total = content["hits"]["total"].clone();
+    let groups = &mut content["aggregations"]["groups"];
+    let buckets = groups["buckets"].as_array_mut().map_or_else(Vec::new, |b| replace(b, Vec::new()));
+
+    // Elasticsearch returns the `after_key` even on the last page, so a page shorter than requested ends the results.
+    let next = if buckets.len() < page_size as usize {
+        None
+    } else {
+        groups.get("after_key").map(Value::to_string)
+    };
+
+    SearchResult {
+        results: buckets.into_iter().map(bucket_to_result).collect(),
+        total: total,
+        groups: content["aggregations"]["group_count"]["value"].as_u64().unwrap_or(0),
+        truncated: false,
+        next: next,
+    }
+}
+
+/// Ranks the groups of a keyword search by `score × count^0.25`, and picks the page after the `page.after` groups
+/// already returned.
+fn parse_ranked_search_result(mut content: Value, page: &Page) -> SearchResult {
+    let total = content["hits"]["total"].clone();
+    let group_count = content["aggregations"]["group_count"]["value"].as_u64().unwrap_or(0);
+    // The documents of the groups beyond `MAX_RANKED_GROUPS` are counted in `sum_other_doc_count`.
+    let truncated = content["aggregations"]["groups"]["sum_other_doc_count"].as_u64().map_or(false, |n| n > 0);
+    let mut buckets = content["aggregations"]["groups"]["buckets"].as_array_mut()
+        .map_or_else(Vec::new, |b| replace(b, Vec::new()));
+
+    let rank = |bucket: &Value| {
+        let score = bucket["top_score"]["value"].as_f64().unwrap_or(0.0);
+        let count = bucket["doc_count"].as_f64().unwrap_or(0.0);
+        score * count.powf(0.25)
+    };
+    buckets.sort_by(|a, b| rank(b).partial_cmp(&rank(a)).unwrap_or(Ordering::Equal));
+
+    let offset = page.after.as_ref().and_then(Value::as_u64).unwrap_or(0) as usize;
+    let end = offset + page.size as usize;
+    let next = if end < buckets.len() { Some(end.to_string()) } else { None };
+    SearchResult {
+        results: buckets.into_iter().skip(offset).take(page.size as usize).map(bucket_to_result).collect(),
+        total: total,
+        groups: group_count,
+        truncated: truncated,
+        next: next,
+    }
+}
+
+/// Converts a group of the search aggregation into a search result, with the number of documents in `_count`, and
+/// the highlighted translations in `_highlight`.
+fn bucket_to_result(mut bucket: Value) -> Value {
+    let mut entry = replace(&mut bucket["entry"]["hits"]["hits"][0], Value::Null);
+    entry["_count"] = bucket["doc_count"].clone();
+    // With `number_of_fragments` being 0, the only fragment is the whole highlighted translation.
+    if let Some(Value::Object(highlight)) = entry.as_object_mut().and_then(|e| e.remove("highlight")) {
+        let highlight = highlight.into_iter().map(|(field, mut fragments)| {
+            (field, replace(&mut fragments[0], Value::Null))
+        }).collect::<Map<_, _>>();
+        // The locales where the keyword is found.
+        entry["_matched"] = highlight.keys().cloned().collect::<Vec<_>>().into();
+        entry["_highlight"] = Value::Object(highlight);
+    }
+    entry
+}
+
+/// Fills the missing target translations of each search result from the fallback locales, ending with the development
+/// language of the document. The locale actually used is recorded in the `_fallback` object of the result.
+fn fill_fallbacks(results: &mut [Value], targets: &[&str]) {
+    let chains = targets.iter().map(|&t| (t, fallback_locale_ids(t))).collect::<Vec<_>>();
+    for result in results {
+        let source_locale = result["_source"]["SOURCE_LOCALE"].as_str().map(str::to_owned);
+        for &(target, ref chain) in &chains {
+            if result["_source"].get(target).is_some() {
+                continue;
             }
-        };
+            let found = chain.iter().chain(&source_locale).filter_map(|locale| {
+                result["_source"].get(locale).map(|value| (locale.clone(), value.clone()))
+            }).next();
+            if let Some((locale, value)) = found {
+                result["_source"][target] = value;
+                result["_fallback"][target] = Value::from(locale);
+            }
+        }
     }
+}
 
-    let mut stack = vec![&mut content["aggregations"]];
-    while let Some(aggs) = stack.pop().and_then(Value::as_object_mut) {
-        let doc_count = aggs.get("doc_count").and_then(Value::as_u64).unwrap_or(0);
-        for (key, value) in aggs.iter_mut() {
-            match &**key {
-                "key" | "doc_count" => {},
-                "entry" => {
-                    let mut entry = replace(&mut value["hits"]["hits"][0], Value::Null);
-                    entry["_count"] = Value::from(doc_count);
-                    result.push(entry);
-                },
-                _ => stack.extend(try_opt!(value["buckets"].as_array_mut()).iter_mut()),
+/// Compares two build identifiers, treating runs of digits as numbers, so `9A334` < `14E304` < `14E5249a` < `15A372`.
+pub fn compare_builds(a: &str, b: &str) -> Ordering {
+    fn split_runs(s: &str) -> Vec<&str> {
+        let mut runs = Vec::new();
+        let mut start = 0;
+        let mut was_digit = None;
+        for (i, c) in s.char_indices() {
+            let is_digit = c.is_ascii_digit();
+            if was_digit == Some(!is_digit) {
+                runs.push(&s[start..i]);
+                start = i;
             }
+            was_digit = Some(is_digit);
+        }
+        if start < s.len() {
+            runs.push(&s[start..]);
+        }
+        runs
+    }
+
+    for (x, y) in split_runs(a).into_iter().zip(split_runs(b)) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(m), Ok(n)) => m.cmp(&n),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+#[test]
+fn test_compare_builds() {
+    let mut builds = vec!["15A372", "14E5249a", "9A334", "14E304", "14E304b", "10.0.17763", "10.0.9200"];
+    builds.sort_by(|a, b| compare_builds(a, b));
+    assert_eq!(builds, vec!["9A334", "10.0.9200", "10.0.17763", "14E304", "14E304b", "14E5249a", "15A372"]);
+}
+
+/// Orders the entries of a key by build, and lists the translation of each locale in every build together with its
+/// character-level diff from the previous build.
+fn history_timeline(mut entries: Vec<Value>, locale: Option<&str>) -> Value {
+    fn build_of(entry: &Value) -> &str {
+        entry["_source"]["BUILD"].as_str().unwrap_or("")
+    }
+    fn text_of(value: &Value) -> Option<String> {
+        match *value {
+            Value::Null => None,
+            Value::String(ref s) => Some(s.clone()),
+            ref v => Some(v.to_string()),
+        }
+    }
+    fn edit_to_json(edit: &TextEdit) -> Value {
+        match *edit {
+            TextEdit::Equal(s) => json!(["=", s]),
+            TextEdit::Delete(s) => json!(["-", s]),
+            TextEdit::Insert(s) => json!(["+", s]),
         }
     }
 
-    result
+    entries.sort_by(|a, b| compare_builds(build_of(a), build_of(b)));
+
+    let locales = match locale {
+        Some(locale) => vec![locale.to_owned()],
+        None => {
+            let fields = entries.iter().flat_map(|e| e["_source"].as_object()).flat_map(|s| s.keys());
+            let mut locales = fields.filter(|f| is_locale_field(f)).cloned().collect::<Vec<_>>();
+            locales.sort();
+            locales.dedup();
+            locales
+        },
+    };
+
+    let mut history = Map::new();
+    for locale in locales {
+        let mut previous: Option<String> = None;
+        let timeline = entries.iter().enumerate().map(|(i, entry)| {
+            let text = text_of(&entry["_source"][&locale]);
+            let changed = i > 0 && text != previous;
+            let edits = match (&previous, &text) {
+                (&Some(ref old), &Some(ref new)) if changed => {
+                    Value::Array(diff_text(old, new).iter().map(edit_to_json).collect())
+                },
+                _ => Value::Null,
+            };
+            let item = json!({
+                "build": entry["_source"]["BUILD"],
+                "id": entry["_id"],
+                "value": text,
+                "changed": changed,
+                "diff": edits,
+            });
+            previous = text;
+            item
+        }).collect::<Vec<_>>();
+        history.insert(locale, Value::Array(timeline));
+    }
+
+    let builds = entries.iter().map(|e| e["_source"]["BUILD"].clone()).collect::<Vec<_>>();
+    json!({
+        "builds": builds,
+        "history": history,
+    })
 }
 
 fn reply_json<T: ?Sized + Serialize>(r: &T) -> IronResult<Response> {
@@ -181,16 +803,161 @@ fn main() {
         type_: opts.type_,
     });
     let languages_searcher = Arc::clone(&search_searcher);
+    let entry_searcher = Arc::clone(&search_searcher);
+    let bundles_searcher = Arc::clone(&search_searcher);
+    let diff_searcher = Arc::clone(&search_searcher);
+    let history_searcher = Arc::clone(&search_searcher);
+    let coverage_searcher = Arc::clone(&search_searcher);
 
     let mut mount = Mount::new();
     mount.mount("/languages", move |_: &mut Request| reply_json(&languages_searcher.list_languages()?));
     mount.mount("/search", move |req: &mut Request| -> IronResult<Response> {
         let params = req.get::<Params>().unwrap();
-        let (source, target, keyword) = match (params.get("f"), params.get("t"), params.get("k")) {
-            (Some(&PVString(ref f)), Some(&PVString(ref t)), Some(&PVString(ref k))) => (f, t, k),
+        let (source, target) = match (params.get("f"), params.get("t")) {
+            (Some(&PVString(ref f)), Some(&PVString(ref t))) => (f, t),
             _ => return Ok(Response::with((status::BadRequest, "[]"))),
         };
-        reply_json(&search_searcher.search(source, target.split(','), keyword)?)
+        let keyword = match params.get("k") {
+            Some(&PVString(ref k)) => &**k,
+            _ => "",
+        };
+        let mode = match params.get("mode") {
+            Some(&PVString(ref m)) => SearchMode::from_param(m),
+            _ => Some(SearchMode::Match),
+        };
+        let mode = match mode {
+            Some(mode) => mode,
+            None => return Ok(Response::with((status::BadRequest, "[]"))),
+        };
+        let mut filters = SearchFilters::from_params(&params);
+        let page = match Page::from_params(&params) {
+            Some(page) => page,
+            None => return Ok(Response::with((status::BadRequest, "[]"))),
+        };
+        if keyword.is_empty() && filters.is_empty() {
+            return Ok(Response::with((status::BadRequest, "[]")));
+        }
+        let targets = target.split(',').collect::<Vec<_>>();
+
+        // The expansion ratio is filtered and sorted by in the first target locale.
+        filters.ratio_locale = targets.first().map(|&t| t.to_owned());
+        let ratio_order = match params.get("sort") {
+            Some(&PVString(ref sort)) if sort == "ratio" => targets.first().cloned(),
+            _ => None,
+        };
+
+        // The keyword is matched against the source locale by default, or any other locale for reverse lookup. `*`
+        // matches all locales at once.
+        let matched_locale = match params.get("in") {
+            Some(&PVString(ref l)) if !l.is_empty() => &**l,
+            _ => &**source,
+        };
+        let query = KeywordQuery {
+            keyword: keyword,
+            mode: mode,
+            locales: if matched_locale == "*" {
+                search_searcher.locale_fields()?
+            } else {
+                vec![matched_locale.to_owned()]
+            },
+        };
+
+        // Group by the matched locale first, so the results are listed by the text found.
+        let mut group_by = Vec::new();
+        let matched_group = Some(matched_locale).filter(|&l| l != "*");
+        for locale in matched_group.into_iter().chain(once(&**source)).chain(targets.iter().cloned()) {
+            if !group_by.contains(&locale) {
+                group_by.push(locale);
+            }
+        }
+
+        let mut result = search_searcher.search(&group_by, &query, &filters, &page, ratio_order)?;
+        if let Some(&PVString(ref fallback)) = params.get("fallback") {
+            if fallback == "1" {
+                fill_fallbacks(&mut result.results, &targets);
+            }
+        }
+        reply_json(&json!({
+            "results": result.results,
+            "total": result.total,
+            "groups": result.groups,
+            "truncated": result.truncated,
+            "next": result.next,
+        }))
+    });
+    mount.mount("/entry", move |req: &mut Request| -> IronResult<Response> {
+        let id = decode(&req.url.path().join("/"));
+        if !id.is_empty() {
+            return match entry_searcher.get_entry(&id)? {
+                Some(entry) => reply_json(&entry),
+                None => Ok(Response::with((status::NotFound, "null"))),
+            };
+        }
+
+        let params = req.get::<Params>().unwrap();
+        match (params.get("bundle"), params.get("file"), params.get("key")) {
+            (Some(&PVString(ref bundle)), Some(&PVString(ref file)), Some(&PVString(ref key))) => {
+                reply_json(&entry_searcher.find_entries(bundle, file, key)?)
+            },
+            _ => Ok(Response::with((status::BadRequest, "[]"))),
+        }
+    });
+    mount.mount("/bundles", move |req: &mut Request| -> IronResult<Response> {
+        let segments = req.url.path().into_iter().map(decode).collect::<Vec<_>>();
+        let params = req.get::<Params>().unwrap();
+        let build = match params.get("build") {
+            Some(&PVString(ref build)) if !build.is_empty() => Some(&**build),
+            _ => None,
+        };
+        let segments = segments.iter().map(|s| &**s).filter(|s| !s.is_empty()).collect::<Vec<_>>();
+        match &*segments {
+            &[] => reply_json(&bundles_searcher.list_bundles(build)?),
+            &[bundle, "files"] => reply_json(&bundles_searcher.list_files(bundle, build)?),
+            &[bundle, "files", file] => reply_json(&bundles_searcher.list_file_entries(bundle, file, build)?),
+            _ => Ok(Response::with((status::NotFound, "[]"))),
+        }
+    });
+    mount.mount("/diff", move |req: &mut Request| -> IronResult<Response> {
+        let params = req.get::<Params>().unwrap();
+        let (old, new) = match (params.get("from"), params.get("to")) {
+            (Some(&PVString(ref from)), Some(&PVString(ref to))) => (from, to),
+            _ => return Ok(Response::with((status::BadRequest, "[]"))),
+        };
+        let old = diff_searcher.read_snapshot(old)?;
+        let new = diff_searcher.read_snapshot(new)?;
+        let changes = diff(&old, &new);
+        match params.get("format") {
+            Some(&PVString(ref format)) if format == "csv" => {
+                let mut resp = Response::with((status::Ok, diff::to_csv(&changes)));
+                resp.headers.set(ContentType("text/csv; charset=utf-8".parse().unwrap()));
+                Ok(resp)
+            },
+            _ => reply_json(&diff::to_json(&changes)),
+        }
+    });
+    mount.mount("/history", move |req: &mut Request| -> IronResult<Response> {
+        let params = req.get::<Params>().unwrap();
+        let entries = match (params.get("bundle"), params.get("file"), params.get("key")) {
+            (Some(&PVString(ref bundle)), Some(&PVString(ref file)), Some(&PVString(ref key))) => {
+                history_searcher.find_entries(bundle, file, key)?
+            },
+            _ => return Ok(Response::with((status::BadRequest, "{}"))),
+        };
+        let locale = match params.get("locale") {
+            Some(&PVString(ref locale)) if !locale.is_empty() => Some(&**locale),
+            _ => None,
+        };
+        reply_json(&history_timeline(entries, locale))
+    });
+    mount.mount("/coverage", move |req: &mut Request| -> IronResult<Response> {
+        let params = req.get::<Params>().unwrap();
+        let param = |name| match params.get(name) {
+            Some(&PVString(ref value)) if !value.is_empty() => Some(&**value),
+            _ => None,
+        };
+        let bundle = param("bundle");
+        let coverage = coverage_searcher.read_coverage(param("build"), bundle)?;
+        reply_json(&coverage::to_json(&coverage.reports(), bundle.is_some()))
     });
     if cfg!(debug_assertions) {
         mount.mount("/", Static::new(Path::new(file!()).with_file_name("home.html")));
@@ -0,0 +1,600 @@
+//! Read and fill translation files of apps.
+//!
+//! The `suggest` command takes a file to translate and writes a copy with the suggested translations filled in. Three
+//! formats are supported:
+//!
+//! * `*.strings` files hold a single language, so the output is the file of the target locale, with each suggestion
+//!     annotated by a comment.
+//! * `*.xcstrings` string catalogs hold all languages. Suggestions are added as `needs_review` string units, and
+//!     annotated in the comment of the key.
+//! * `*.xliff` (or `*.xlf`) XLIFF 1.2 files exported by Xcode. Suggestions are added as
+//!     `needs-review-translation` targets of the trans-units without one, and annotated by a `<note>`.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+use serde_json::{self, Value};
+use xml::name::OwnedName;
+use xml::reader::{ParserConfig, XmlEvent};
+
+use suggest::Suggestion;
+use error::{ErrorKind, Result};
+
+/// The format of a translation file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CatalogFormat {
+    Strings,
+    XcStrings,
+    Xliff,
+}
+
+impl CatalogFormat {
+    /// Guesses the format from the file extension.
+    pub fn from_path(path: &Path) -> Option<CatalogFormat> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("strings") => Some(CatalogFormat::Strings),
+            Some("xcstrings") => Some(CatalogFormat::XcStrings),
+            Some("xliff") | Some("xlf") => Some(CatalogFormat::Xliff),
+            _ => None,
+        }
+    }
+}
+
+/// Name of the tool in the annotations.
+const TOOL_NAME: &str = "lproj2es";
+
+/// Describes a suggestion for reviewers.
+fn annotation(suggestion: &Suggestion) -> String {
+    format!("{}: suggested with confidence {:.2}, matching {:?}", TOOL_NAME, suggestion.confidence,
+        suggestion.matched_source)
+}
+
+/// An entry of a `*.strings` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringsEntry {
+    pub key: String,
+    pub value: String,
+    /// The comment right before the entry, without the delimiters.
+    pub comment: Option<String>,
+}
+
+/// Parses the text of an old-style `*.strings` file.
+///
+/// The `plist` crate only reads XML and binary property lists, while the `*.strings` files of apps are almost always
+/// written in the old-style `"key" = "value";` syntax.
+pub fn parse_strings(text: &str) -> Result<Vec<StringsEntry>> {
+    let mut parser = StringsParser { chars: text.chars().collect(), pos: 0 };
+    let mut entries = Vec::new();
+    loop {
+        let comment = parser.skip_space_and_comments();
+        if parser.peek().is_none() {
+            return Ok(entries);
+        }
+        let key = parser.token()?;
+        parser.skip_space_and_comments();
+        let value = if parser.eat(';') {
+            key.clone()
+        } else {
+            parser.expect('=')?;
+            parser.skip_space_and_comments();
+            let value = parser.token()?;
+            parser.skip_space_and_comments();
+            parser.expect(';')?;
+            value
+        };
+        entries.push(StringsEntry { key: key, value: value, comment: comment });
+    }
+}
+
+struct StringsParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl StringsParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn line(&self) -> usize {
+        self.chars[..self.pos].iter().filter(|&&c| c == '\n').count() + 1
+    }
+
+    fn error(&self) -> ::error::Error {
+        ErrorKind::InvalidStrings(self.line()).into()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Skips whitespace and comments, and returns the last comment.
+    fn skip_space_and_comments(&mut self) -> Option<String> {
+        let mut comment = None;
+        loop {
+            if self.peek().map_or(false, char::is_whitespace) {
+                self.pos += 1;
+            } else if self.starts_with("/*") {
+                let start = self.pos + 2;
+                let mut end = start;
+                while end < self.chars.len() && !(self.chars[end] == '*' && self.chars.get(end + 1) == Some(&'/')) {
+                    end += 1;
+                }
+                comment = Some(self.chars[start..end].iter().collect::<String>().trim().to_owned());
+                self.pos = (end + 2).min(self.chars.len());
+            } else if self.starts_with("//") {
+                let start = self.pos + 2;
+                let end = (start..self.chars.len()).find(|&i| self.chars[i] == '\n').unwrap_or(self.chars.len());
+                comment = Some(self.chars[start..end].iter().collect::<String>().trim().to_owned());
+                self.pos = end;
+            } else {
+                return comment;
+            }
+        }
+    }
+
+    /// Reads a quoted or unquoted string.
+    fn token(&mut self) -> Result<String> {
+        if !self.eat('"') {
+            let start = self.pos;
+            while self.peek().map_or(false, |c| c.is_alphanumeric() || "_$:./-".contains(c)) {
+                self.pos += 1;
+            }
+            if self.pos == start {
+                return Err(self.error());
+            }
+            return Ok(self.chars[start..self.pos].iter().collect());
+        }
+
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error()),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                },
+                Some('\\') => {
+                    self.pos += 1;
+                    let c = self.peek().ok_or_else(|| self.error())?;
+                    self.pos += 1;
+                    match c {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        'U' | 'u' => {
+                            let hex = self.chars.get(self.pos..self.pos + 4).ok_or_else(|| self.error())?;
+                            let hex = hex.iter().collect::<String>();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error())?;
+                            s.push(::std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                            self.pos += 4;
+                        },
+                        c => s.push(c),
+                    }
+                },
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+    }
+}
+
+/// Quotes a string for a `*.strings` file.
+fn quote_strings(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a comment of a `*.strings` file.
+fn write_strings_comment(out: &mut String, comment: &str) {
+    out.push_str("/* ");
+    out.push_str(&comment.replace("*/", "* /"));
+    out.push_str(" */\n");
+}
+
+/// Translates a `*.strings` file into a locale. Entries without a suggestion keep the source text.
+pub fn fill_strings<F>(text: &str, locale: &str, mut suggest: F) -> Result<String>
+    where F: FnMut(&str, &str) -> Result<Option<Suggestion>>
+{
+    let mut out = String::new();
+    for entry in parse_strings(text)? {
+        if let Some(ref comment) = entry.comment {
+            write_strings_comment(&mut out, comment);
+        }
+        let value = match suggest(&entry.value, locale)? {
+            Some(suggestion) => {
+                write_strings_comment(&mut out, &annotation(&suggestion));
+                suggestion.text
+            },
+            None => {
+                write_strings_comment(&mut out, &format!("{}: no suggestion found", TOOL_NAME));
+                entry.value
+            },
+        };
+        out.push_str(&format!("{} = {};\n\n", quote_strings(&entry.key), quote_strings(&value)));
+    }
+    Ok(out)
+}
+
+#[test]
+fn test_fill_strings() {
+    let text = r#"
+        /* Title of the cancel button */
+        "CANCEL" = "Cancel";
+        // Untranslatable
+        "SEARCH_\"ALL\"" = "Search \U201Call\U201D\n";
+        plain_key = plain_value;
+    "#;
+    assert_eq!(parse_strings(text).unwrap(), vec![
+        StringsEntry {
+            key: "CANCEL".to_owned(),
+            value: "Cancel".to_owned(),
+            comment: Some("Title of the cancel button".to_owned()),
+        },
+        StringsEntry {
+            key: "SEARCH_\"ALL\"".to_owned(),
+            value: "Search “all”\n".to_owned(),
+            comment: Some("Untranslatable".to_owned()),
+        },
+        StringsEntry { key: "plain_key".to_owned(), value: "plain_value".to_owned(), comment: None },
+    ]);
+    assert_eq!(parse_strings("\"A\" = \"B\"").unwrap_err().to_string(), "invalid *.strings syntax at line 1");
+
+    let filled = fill_strings(text, "de", |source, locale| {
+        assert_eq!(locale, "de");
+        Ok(if source == "Cancel" {
+            Some(Suggestion { text: "Abbrechen".to_owned(), confidence: 0.8, matched_source: "Cancel".to_owned() })
+        } else {
+            None
+        })
+    }).unwrap();
+    assert_eq!(filled, "/* Title of the cancel button */\n\
+                        /* lproj2es: suggested with confidence 0.80, matching \"Cancel\" */\n\
+                        \"CANCEL\" = \"Abbrechen\";\n\n\
+                        /* Untranslatable */\n\
+                        /* lproj2es: no suggestion found */\n\
+                        \"SEARCH_\\\"ALL\\\"\" = \"Search “all”\\n\";\n\n\
+                        /* lproj2es: no suggestion found */\n\
+                        \"plain_key\" = \"plain_value\";\n\n");
+}
+
+/// Adds the suggestions to a `*.xcstrings` string catalog for every locale. Existing translations are kept.
+///
+/// The callback receives the source language of the catalog, the source text and the target locale.
+pub fn fill_xcstrings<F>(text: &str, locales: &[String], mut suggest: F) -> Result<String>
+    where F: FnMut(&str, &str, &str) -> Result<Option<Suggestion>>
+{
+    let mut catalog: Value = serde_json::from_str(text)?;
+    let source_language = catalog["sourceLanguage"].as_str().unwrap_or("en").to_owned();
+    let keys = catalog["strings"].as_object().map_or_else(Vec::new, |s| s.keys().cloned().collect());
+
+    for key in keys {
+        let entry = &mut catalog["strings"][&key];
+        if entry["shouldTranslate"] == Value::Bool(false) {
+            continue;
+        }
+        let source = {
+            let source_localization = &entry["localizations"][&source_language];
+            if source_localization.is_null() {
+                key.clone()
+            } else {
+                match source_localization["stringUnit"]["value"].as_str() {
+                    Some(s) => s.to_owned(),
+                    // plural and device variations are not supported.
+                    None => continue,
+                }
+            }
+        };
+
+        let mut notes = Vec::new();
+        for locale in locales {
+            if !entry["localizations"][locale].is_null() {
+                continue;
+            }
+            if let Some(suggestion) = suggest(&source_language, &source, locale)? {
+                notes.push(format!("{} ({})", annotation(&suggestion), locale));
+                entry["localizations"][locale] = json!({
+                    "stringUnit": {"state": "needs_review", "value": suggestion.text},
+                });
+            }
+        }
+        if !notes.is_empty() {
+            let comment = match entry["comment"].as_str() {
+                Some(c) => format!("{}\n{}", c, notes.join("\n")),
+                None => notes.join("\n"),
+            };
+            entry["comment"] = Value::String(comment);
+        }
+    }
+
+    Ok(serde_json::to_string_pretty(&catalog)?)
+}
+
+#[test]
+fn test_fill_xcstrings() {
+    let text = r#"{
+        "sourceLanguage": "en",
+        "strings": {
+            "Cancel": {},
+            "DONE": {
+                "comment": "Done button",
+                "localizations": {
+                    "en": {"stringUnit": {"state": "translated", "value": "Done"}},
+                    "fr": {"stringUnit": {"state": "translated", "value": "OK"}}
+                }
+            },
+            "%lld items": {"localizations": {"en": {"variations": {}}}},
+            "Version": {"shouldTranslate": false}
+        },
+        "version": "1.0"
+    }"#;
+    let mut requests = Vec::new();
+    let filled = fill_xcstrings(text, &["de".to_owned(), "fr".to_owned()], |source_language, source, locale| {
+        requests.push(format!("{} {} {}", source_language, source, locale));
+        let text = format!("{}-{}", source, locale);
+        Ok(Some(Suggestion { text: text, confidence: 1.0, matched_source: source.to_owned() }))
+    }).unwrap();
+    assert_eq!(requests, vec!["en Cancel de", "en Cancel fr", "en Done de"]);
+
+    let filled: Value = serde_json::from_str(&filled).unwrap();
+    assert_eq!(filled["strings"]["Cancel"]["localizations"]["fr"], json!({
+        "stringUnit": {"state": "needs_review", "value": "Cancel-fr"},
+    }));
+    assert_eq!(filled["strings"]["DONE"]["localizations"]["fr"]["stringUnit"]["value"], "OK");
+    assert_eq!(filled["strings"]["DONE"]["comment"],
+        "Done button\nlproj2es: suggested with confidence 1.00, matching \"Done\" (de)");
+    assert_eq!(filled["version"], "1.0");
+}
+
+/// Escapes the text for XML.
+fn escape_xml(s: &str, attribute: bool) -> Cow<str> {
+    if !s.contains(|c| c == '&' || c == '<' || c == '>' || (attribute && c == '"')) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Formats a qualified name as `prefix:local_name`.
+fn qualified_name(name: &OwnedName) -> String {
+    match name.prefix {
+        Some(ref prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+/// The trans-unit being read.
+#[derive(Debug, Default)]
+struct TransUnit {
+    source: String,
+    in_source: bool,
+    has_target: bool,
+    /// Where the `<target>` should be inserted in the output, right after `</source>`.
+    target_position: Option<usize>,
+}
+
+/// Adds the suggestions to an XLIFF 1.2 file, to every trans-unit without a target.
+///
+/// The callback receives the `source-language` and `target-language` of the `<file>` being read, and the source
+/// text. Files without a `target-language` use the `default_locale`, and are skipped if it is not given either.
+pub fn fill_xliff<R, F>(input: R, default_locale: Option<&str>, mut suggest: F) -> Result<String>
+    where R: Read, F: FnMut(&str, &str, &str) -> Result<Option<Suggestion>>
+{
+    let mut reader = ParserConfig::new().ignore_comments(false).create_reader(input);
+    let mut out = String::new();
+    let mut namespaces: Vec<BTreeMap<String, String>> = Vec::new();
+    let mut languages: Option<(String, Option<String>)> = None;
+    let mut unit: Option<TransUnit> = None;
+
+    loop {
+        match reader.next()? {
+            XmlEvent::StartDocument { standalone, .. } => {
+                out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"");
+                if let Some(standalone) = standalone {
+                    out.push_str(if standalone { " standalone=\"yes\"" } else { " standalone=\"no\"" });
+                }
+                out.push_str("?>\n");
+            },
+            XmlEvent::EndDocument => break,
+            XmlEvent::ProcessingInstruction { name, data } => {
+                out.push_str(&format!("<?{}", name));
+                if let Some(data) = data {
+                    out.push(' ');
+                    out.push_str(&data);
+                }
+                out.push_str("?>");
+            },
+            XmlEvent::Comment(comment) => out.push_str(&format!("<!--{}-->", comment)),
+            XmlEvent::CData(data) => {
+                if let Some(TransUnit { ref mut source, in_source: true, .. }) = unit {
+                    source.push_str(&data);
+                }
+                out.push_str(&format!("<![CDATA[{}]]>", data));
+            },
+            XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => {
+                if let Some(TransUnit { ref mut source, in_source: true, .. }) = unit {
+                    source.push_str(&text);
+                }
+                out.push_str(&escape_xml(&text, false));
+            },
+            XmlEvent::StartElement { name, attributes, namespace } => {
+                let attribute = |local_name: &str| {
+                    attributes.iter().find(|a| a.name.prefix.is_none() && a.name.local_name == local_name)
+                        .map(|a| a.value.clone())
+                };
+                match &*name.local_name {
+                    "file" => {
+                        let source_language = attribute("source-language").unwrap_or_else(|| "en".to_owned());
+                        let target_language = attribute("target-language").or(default_locale.map(String::from));
+                        languages = Some((source_language, target_language));
+                    },
+                    "trans-unit" => unit = Some(TransUnit::default()),
+                    "source" => if let Some(ref mut unit) = unit {
+                        unit.in_source = true;
+                    },
+                    "target" => if let Some(ref mut unit) = unit {
+                        unit.has_target = true;
+                    },
+                    _ => {},
+                }
+
+                out.push('<');
+                out.push_str(&qualified_name(&name));
+                {
+                    let parent = namespaces.last();
+                    for (prefix, uri) in &namespace.0 {
+                        if prefix == "xml" || prefix == "xmlns" {
+                            continue;
+                        }
+                        let inherited = parent.and_then(|p| p.get(prefix)).map_or("", |u| &**u);
+                        if inherited == uri {
+                            continue;
+                        }
+                        if prefix.is_empty() {
+                            out.push_str(&format!(" xmlns=\"{}\"", escape_xml(uri, true)));
+                        } else {
+                            out.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_xml(uri, true)));
+                        }
+                    }
+                }
+                for attribute in &attributes {
+                    let value = escape_xml(&attribute.value, true);
+                    out.push_str(&format!(" {}=\"{}\"", qualified_name(&attribute.name), value));
+                }
+                out.push('>');
+                namespaces.push(namespace.0);
+            },
+            XmlEvent::EndElement { name } => {
+                namespaces.pop();
+                match &*name.local_name {
+                    "file" => languages = None,
+                    "source" => if let Some(ref mut unit) = unit {
+                        unit.in_source = false;
+                        unit.target_position = Some(out.len() + qualified_name(&name).len() + 3);
+                    },
+                    "trans-unit" => if let Some(unit) = unit.take() {
+                        if let (false, Some(position)) = (unit.has_target, unit.target_position) {
+                            let (source_language, target_language) = match languages {
+                                Some((ref s, Some(ref t))) => (s, t),
+                                _ => bail!(ErrorKind::MissingTargetLanguage),
+                            };
+                            if let Some(suggestion) = suggest(source_language, target_language, &unit.source)? {
+                                out.push_str(&format!("<note from=\"{}\">{}</note>", TOOL_NAME,
+                                    escape_xml(&annotation(&suggestion), false)));
+                                let target = format!("<target state=\"needs-review-translation\">{}</target>",
+                                    escape_xml(&suggestion.text, false));
+                                out.insert_str(position, &target);
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+                out.push_str(&format!("</{}>", qualified_name(&name)));
+            },
+        }
+    }
+    out.push('\n');
+    Ok(out)
+}
+
+#[test]
+fn test_fill_xliff() {
+    let xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" version="1.2">
+  <file original="en.lproj/Localizable.strings" source-language="en" target-language="de" datatype="plaintext">
+    <body>
+      <!-- buttons -->
+      <trans-unit id="CANCEL" xml:space="preserve">
+        <source>Cancel &amp; Close</source>
+        <note>Title of the cancel button</note>
+      </trans-unit>
+      <trans-unit id="DONE" xml:space="preserve">
+        <source>Done</source>
+        <target>Fertig</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+    let mut requests = Vec::new();
+    let filled = fill_xliff(xliff.as_bytes(), None, |source_language, target_language, source| {
+        requests.push(format!("{} {} {}", source_language, target_language, source));
+        let text = "Abbrechen & Schließen".to_owned();
+        Ok(Some(Suggestion { text: text, confidence: 0.5, matched_source: "Cancel & Close".to_owned() }))
+    }).unwrap();
+    assert_eq!(requests, vec!["en de Cancel & Close"]);
+    assert_eq!(filled, r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" version="1.2">
+  <file original="en.lproj/Localizable.strings" source-language="en" target-language="de" datatype="plaintext">
+    <body>
+      <!-- buttons -->
+      <trans-unit id="CANCEL" xml:space="preserve">
+        <source>Cancel &amp; Close</source><target state="needs-review-translation">Abbrechen &amp; Schließen</target>
+        <note>Title of the cancel button</note>
+      <note from="lproj2es">lproj2es: suggested with confidence 0.50, matching "Cancel &amp; Close"</note></trans-unit>
+      <trans-unit id="DONE" xml:space="preserve">
+        <source>Done</source>
+        <target>Fertig</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
@@ -0,0 +1,429 @@
+//! Check that the format specifiers of translations match the source.
+//!
+//! A translation of `%d files in %@` must consume the same arguments as the source, e.g. `%2$@ enthält %1$d Dateien`,
+//! otherwise the app prints garbage or crashes when formatting the string. The specifiers follow `printf` with the
+//! CFString additions `%@` (objects) and `%#@name@` (variables defined in `*.stringsdict` files).
+
+use std::collections::BTreeMap;
+use serde_json::Value;
+
+use locales::is_locale_field;
+use lproj::{parse_strings_dict, FORMAT_KEY};
+
+/// The kind of argument consumed by a format specifier. Conversions of the same size are treated as the same type, so
+/// `%d`, `%u` and `%x` are all `Int`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgType {
+    Int,
+    Long,
+    Double,
+    Char,
+    UniChar,
+    CString,
+    UniString,
+    Object,
+    Pointer,
+    /// A `*.stringsdict` variable `%#@name@`.
+    Variable(String),
+}
+
+/// A format specifier found in a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatSpecifier<'a> {
+    /// The specifier as written, e.g. `%1$lld`. This is `*` for the argument of a variable width or precision.
+    pub text: &'a str,
+    /// The explicit 1-based argument index, e.g. 1 for `%1$lld`.
+    pub position: Option<usize>,
+    pub arg_type: ArgType,
+}
+
+/// Parses a decimal number at the start of `s`, returning the number and its length.
+fn parse_number(s: &[u8]) -> Option<(usize, usize)> {
+    let len = s.iter().take_while(|b| b.is_ascii_digit()).count();
+    if len == 0 {
+        return None;
+    }
+    let n = s[..len].iter().fold(0usize, |n, b| n.saturating_mul(10).saturating_add((b - b'0') as usize));
+    Some((n, len))
+}
+
+/// Parses a `*` or `*n$` of a variable width or precision at the start of `s`, returning the specifier and its length.
+fn parse_star(s: &str) -> Option<(FormatSpecifier, usize)> {
+    if !s.starts_with('*') {
+        return None;
+    }
+    let (position, len) = match parse_number(&s.as_bytes()[1..]) {
+        Some((n, len)) if s.as_bytes().get(1 + len) == Some(&b'$') => (Some(n), len + 2),
+        _ => (None, 1),
+    };
+    let spec = FormatSpecifier {
+        text: &s[..len],
+        position: position,
+        arg_type: ArgType::Int,
+    };
+    Some((spec, len))
+}
+
+/// Finds all format specifiers in a string, in order of appearance. `%%` and invalid specifiers are skipped.
+pub fn parse_format_specifiers(s: &str) -> Vec<FormatSpecifier> {
+    let bytes = s.as_bytes();
+    let mut specifiers = Vec::new();
+    let mut i = 0;
+
+    while let Some(offset) = s[i..].find('%') {
+        let start = i + offset;
+        let mut j = start + 1;
+        i = j;
+        if bytes.get(j) == Some(&b'%') {
+            i = j + 1;
+            continue;
+        }
+
+        let mut position = None;
+        if let Some((n, len)) = parse_number(&bytes[j..]) {
+            if bytes.get(j + len) == Some(&b'$') {
+                position = Some(n);
+                j += len + 1;
+            }
+        }
+
+        let mut alternate = false;
+        while let Some(&flag) = bytes.get(j) {
+            match flag {
+                b'#' => alternate = true,
+                b'-' | b'+' | b' ' | b'0' | b'\'' => {},
+                _ => break,
+            }
+            j += 1;
+        }
+
+        let mut stars = Vec::new();
+        if let Some((star, len)) = parse_star(&s[j..]) {
+            stars.push(star);
+            j += len;
+        } else if let Some((_, len)) = parse_number(&bytes[j..]) {
+            j += len;
+        }
+        if bytes.get(j) == Some(&b'.') {
+            j += 1;
+            if let Some((star, len)) = parse_star(&s[j..]) {
+                stars.push(star);
+                j += len;
+            } else if let Some((_, len)) = parse_number(&bytes[j..]) {
+                j += len;
+            }
+        }
+
+        let length_modifiers = ["hh", "h", "ll", "l", "q", "L", "z", "t", "j"];
+        let length = length_modifiers.iter().find(|m| s[j..].starts_with(*m)).map_or("", |m| *m);
+        j += length.len();
+        let is_long = match length {
+            "l" | "ll" | "q" | "z" | "t" | "j" => true,
+            _ => false,
+        };
+
+        let arg_type = match bytes.get(j) {
+            Some(&b'@') if alternate => {
+                let name_len = match s[j + 1..].find('@') {
+                    Some(len) => len,
+                    None => continue,
+                };
+                let name = s[j + 1..j + 1 + name_len].to_owned();
+                j += name_len + 1;
+                ArgType::Variable(name)
+            },
+            Some(&b'd') | Some(&b'i') | Some(&b'o') | Some(&b'u') | Some(&b'x') | Some(&b'X') =>
+                if is_long { ArgType::Long } else { ArgType::Int },
+            Some(&b'D') | Some(&b'O') | Some(&b'U') => ArgType::Long,
+            Some(&b'f') | Some(&b'F') | Some(&b'e') | Some(&b'E') | Some(&b'g') | Some(&b'G') | Some(&b'a') |
+            Some(&b'A') => ArgType::Double,
+            Some(&b'c') => if is_long { ArgType::UniChar } else { ArgType::Char },
+            Some(&b'C') => ArgType::UniChar,
+            Some(&b's') => if is_long { ArgType::UniString } else { ArgType::CString },
+            Some(&b'S') => ArgType::UniString,
+            Some(&b'@') => ArgType::Object,
+            Some(&b'p') | Some(&b'n') => ArgType::Pointer,
+            _ => continue,
+        };
+        j += 1;
+
+        specifiers.extend(stars);
+        specifiers.push(FormatSpecifier {
+            text: &s[start..j],
+            position: position,
+            arg_type: arg_type,
+        });
+        i = j;
+    }
+
+    specifiers
+}
+
+#[test]
+fn test_parse_format_specifiers() {
+    fn types(s: &str) -> Vec<(&str, Option<usize>, ArgType)> {
+        parse_format_specifiers(s).into_iter().map(|f| (f.text, f.position, f.arg_type)).collect()
+    }
+
+    assert_eq!(types("100%% done"), vec![]);
+    assert_eq!(types("%d files in %@"), vec![
+        ("%d", None, ArgType::Int),
+        ("%@", None, ArgType::Object),
+    ]);
+    assert_eq!(types("%2$@ enthält %1$lld Dateien"), vec![
+        ("%2$@", Some(2), ArgType::Object),
+        ("%1$lld", Some(1), ArgType::Long),
+    ]);
+    assert_eq!(types("%-*.2f, %#@files@"), vec![
+        ("*", None, ArgType::Int),
+        ("%-*.2f", None, ArgType::Double),
+        ("%#@files@", None, ArgType::Variable("files".to_owned())),
+    ]);
+    assert_eq!(types("%.*3$s %5S %lu%"), vec![
+        ("*3$", Some(3), ArgType::Int),
+        ("%.*3$s", None, ArgType::CString),
+        ("%5S", None, ArgType::UniString),
+        ("%lu", None, ArgType::Long),
+    ]);
+}
+
+/// The kind of a mismatch between the format specifiers of a translation and the source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The translation consumes a different number of arguments.
+    Count,
+    /// An argument is formatted with an incompatible type.
+    Type,
+    /// Arguments are skipped, reused with different types, or positional and sequential specifiers are mixed.
+    Position,
+}
+
+impl IssueKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            IssueKind::Count => "count",
+            IssueKind::Type => "type",
+            IssueKind::Position => "position",
+        }
+    }
+}
+
+/// A mismatch between the format specifiers of a translation and the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, message: String) -> Issue {
+        Issue { kind: kind, message: message }
+    }
+}
+
+/// Assigns the specifiers to their 1-based argument indices. Conflicting types of the same argument are reported.
+fn arguments<'a>(specifiers: &[FormatSpecifier<'a>], issues: &mut Vec<Issue>)
+    -> BTreeMap<usize, FormatSpecifier<'a>>
+{
+    let mut arguments = BTreeMap::new();
+    let mut next = 1;
+    for spec in specifiers {
+        let index = spec.position.unwrap_or_else(|| {
+            next += 1;
+            next - 1
+        });
+        let existing = arguments.entry(index).or_insert_with(|| spec.clone());
+        if existing.arg_type != spec.arg_type {
+            issues.push(Issue::new(IssueKind::Position, format!(
+                "argument {} is used as both `{}` and `{}`", index, existing.text, spec.text,
+            )));
+        }
+    }
+    arguments
+}
+
+/// Compares the format specifiers of a translation with the source.
+///
+/// If `allow_missing` is true, arguments may be left out of the translation, which is the case for plural forms such
+/// as the `one` form `a file` of `%d files`.
+pub fn check_format(source: &str, translation: &str, allow_missing: bool) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let source_specs = parse_format_specifiers(source);
+    let translation_specs = parse_format_specifiers(translation);
+
+    let positional_count = translation_specs.iter().filter(|s| s.position.is_some()).count();
+    if positional_count != 0 && positional_count != translation_specs.len() {
+        let message = "mixes positional and sequential specifiers".to_owned();
+        issues.push(Issue::new(IssueKind::Position, message));
+    }
+
+    let source_args = arguments(&source_specs, &mut Vec::new());
+    let translation_args = arguments(&translation_specs, &mut issues);
+    let source_count = source_args.keys().next_back().cloned().unwrap_or(0);
+    let translation_count = translation_args.keys().next_back().cloned().unwrap_or(0);
+    if translation_count > source_count || (!allow_missing && translation_count < source_count) {
+        issues.push(Issue::new(IssueKind::Count, format!(
+            "uses {} arguments but the source uses {}", translation_count, source_count,
+        )));
+    }
+
+    for (index, source_spec) in &source_args {
+        match translation_args.get(index) {
+            Some(spec) if spec.arg_type != source_spec.arg_type => {
+                issues.push(Issue::new(IssueKind::Type, format!(
+                    "argument {} is `{}` but `{}` in the source", index, spec.text, source_spec.text,
+                )));
+            },
+            None if !allow_missing && *index < translation_count => {
+                issues.push(Issue::new(IssueKind::Position, format!(
+                    "argument {} (`{}`) is skipped", index, source_spec.text,
+                )));
+            },
+            _ => {},
+        }
+    }
+
+    issues
+}
+
+#[test]
+fn test_check_format() {
+    assert_eq!(check_format("%d files in %@", "%2$@ enthält %1$d Dateien", false), vec![]);
+    assert_eq!(check_format("%d files", "%ld fichiers", false), vec![
+        Issue::new(IssueKind::Type, "argument 1 is `%ld` but `%d` in the source".to_owned()),
+    ]);
+    assert_eq!(check_format("%@ and %@", "%@", false), vec![
+        Issue::new(IssueKind::Count, "uses 1 arguments but the source uses 2".to_owned()),
+    ]);
+    assert_eq!(check_format("%@ and %@", "%2$d %@", false), vec![
+        Issue::new(IssueKind::Position, "mixes positional and sequential specifiers".to_owned()),
+        Issue::new(IssueKind::Type, "argument 2 is `%2$d` but `%@` in the source".to_owned()),
+    ]);
+    assert_eq!(check_format("%@ and %@", "%2$@", false), vec![
+        Issue::new(IssueKind::Position, "argument 1 (`%@`) is skipped".to_owned()),
+    ]);
+    assert_eq!(check_format("%d", "%1$d %1$@", false), vec![
+        Issue::new(IssueKind::Position, "argument 1 is used as both `%1$d` and `%1$@`".to_owned()),
+    ]);
+    assert_eq!(check_format("%d files", "a file", true), vec![]);
+    assert_eq!(check_format("%d files", "%@ file", true), vec![
+        Issue::new(IssueKind::Type, "argument 1 is `%@` but `%d` in the source".to_owned()),
+    ]);
+}
+
+/// Compares a `*.stringsdict` entry of a translation with the source. The format keys are compared strictly, while
+/// every plural form of a variable is compared with the `other` form of the source.
+fn check_strings_dict(source: &BTreeMap<String, Value>, translation: &BTreeMap<String, Value>) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let format_key = |dict: &BTreeMap<String, Value>| dict.get(FORMAT_KEY).and_then(|v| v.as_str()).map(String::from);
+    if let (Some(s), Some(t)) = (format_key(source), format_key(translation)) {
+        issues.extend(check_format(&s, &t, false));
+    }
+
+    for (name, variable) in translation {
+        let forms = match variable.as_object() {
+            Some(forms) => forms,
+            None => continue,
+        };
+        let source_other = source.get(name).and_then(|v| v["other"].as_str());
+        let source_other = match source_other {
+            Some(s) => s,
+            None => continue,
+        };
+        for (form, text) in forms {
+            let text = match text.as_str() {
+                Some(t) if !form.starts_with("NSString") => t,
+                _ => continue,
+            };
+            issues.extend(check_format(source_other, text, true).into_iter().map(|issue| Issue {
+                message: format!("`{}.{}` {}", name, form, issue.message),
+                ..issue
+            }));
+        }
+    }
+
+    issues
+}
+
+/// Gets the source translation of an indexed document, i.e. the translation of its `SOURCE_LOCALE`, or the `BASE`
+/// translation if there is none.
+pub fn source_text(document: &Value) -> Option<&str> {
+    let source_locale = document["SOURCE_LOCALE"].as_str().unwrap_or("");
+    document[source_locale].as_str().or_else(|| document["BASE"].as_str())
+}
+
+/// Checks the format specifiers of every translation of an indexed document against the translation of its
+/// `SOURCE_LOCALE`. Returns the issues found with their locale IDs.
+pub fn check_document(document: &Value) -> Vec<(String, Issue)> {
+    let source_locale = document["SOURCE_LOCALE"].as_str().unwrap_or("");
+    let source = match source_text(document) {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    let source_dict = parse_strings_dict(source);
+
+    let mut issues = Vec::new();
+    for (locale, translation) in document.as_object().into_iter().flat_map(|d| d.iter()) {
+        if !is_locale_field(locale) || locale == source_locale {
+            continue;
+        }
+        let translation = match translation.as_str() {
+            Some(t) => t,
+            None => continue,
+        };
+        let locale_issues = match (&source_dict, parse_strings_dict(translation)) {
+            (&Some(ref s), Some(ref t)) => check_strings_dict(s, t),
+            (&None, None) => check_format(source, translation, false),
+            _ => continue,
+        };
+        issues.extend(locale_issues.into_iter().map(|issue| (locale.clone(), issue)));
+    }
+    issues
+}
+
+#[test]
+fn test_check_document() {
+    let document = json!({
+        "SOURCE_LOCALE": "en_US",
+        "FILE": "Files.strings",
+        "KEY": "FILES_IN_FOLDER",
+        "en_US": "%d files in %@",
+        "de_DE": "%2$@ enthält %1$d Dateien",
+        "fr_FR": "%@ fichiers dans %d",
+    });
+    assert_eq!(check_document(&document), vec![
+        ("fr_FR".to_owned(), Issue::new(IssueKind::Type, "argument 1 is `%@` but `%d` in the source".to_owned())),
+        ("fr_FR".to_owned(), Issue::new(IssueKind::Type, "argument 2 is `%d` but `%@` in the source".to_owned())),
+    ]);
+
+    let document = json!({
+        "SOURCE_LOCALE": "en",
+        "en": r#"{"NSStringLocalizedFormatKey": "%#@files@", "files": {"one": "a file", "other": "%d files"}}"#,
+        "ja": r#"{"NSStringLocalizedFormatKey": "%#@files@", "files": {"other": "%ld個のファイル"}}"#,
+        "ru": r#"{"NSStringLocalizedFormatKey": "%#@file@", "files": {"one": "%d файл", "few": "%d файла"}}"#,
+    });
+    let issues = check_document(&document).into_iter().map(|(l, i)| (l, i.message)).collect::<Vec<_>>();
+    assert_eq!(issues, vec![
+        ("ja".to_owned(), "`files.other` argument 1 is `%ld` but `%d` in the source".to_owned()),
+        ("ru".to_owned(), "argument 1 is `%#@file@` but `%#@files@` in the source".to_owned()),
+    ]);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
@@ -0,0 +1,222 @@
+//! Terminology consistency across bundles.
+//!
+//! The same source text, like `Cancel`, should usually be translated the same way everywhere. Translations are grouped
+//! by their source text, and every locale using more than one distinct translation for the same text is reported. The
+//! inconsistencies are ranked by the number of translations deviating from the most common one.
+
+use std::collections::HashMap;
+use serde_json::Value;
+
+use diff::csv_field;
+use locales::is_locale_field;
+use lproj::is_strings_dict;
+
+/// Number of example entries kept for each distinct translation.
+const MAX_EXAMPLES: usize = 3;
+
+/// An entry using a translation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub bundle_id: String,
+    pub file: String,
+    pub key: String,
+}
+
+/// How often a translation is used for a source text.
+#[derive(Debug, Default)]
+pub struct Usage {
+    pub count: usize,
+    /// The first few entries using the translation.
+    pub examples: Vec<Occurrence>,
+}
+
+/// Collects the translations of every source text.
+#[derive(Debug, Default)]
+pub struct Terminology {
+    // {(source locale, source text) => {locale => {translation => usage}}}
+    terms: HashMap<(String, String), HashMap<String, HashMap<String, Usage>>>,
+}
+
+impl Terminology {
+    /// Adds an indexed document. Bundles are identified by the `BUNDLE_ID`, or the `BUNDLE` path if missing.
+    pub fn add_document(&mut self, document: &Value) {
+        let source_locale = document["SOURCE_LOCALE"].as_str().unwrap_or("");
+        let source = match document[source_locale].as_str() {
+            Some(s) if !s.trim().is_empty() && !is_strings_dict(s) => s,
+            _ => return,
+        };
+        let occurrence = Occurrence {
+            bundle_id: document["BUNDLE_ID"].as_str().or_else(|| document["BUNDLE"].as_str()).unwrap_or("").to_owned(),
+            file: document["FILE"].as_str().unwrap_or("").to_owned(),
+            key: document["KEY"].as_str().unwrap_or("").to_owned(),
+        };
+
+        let term = self.terms.entry((source_locale.to_owned(), source.to_owned())).or_insert_with(HashMap::new);
+        let fields = document.as_object().into_iter().flat_map(|d| d.iter());
+        for (locale, translation) in fields.filter(|&(field, _)| is_locale_field(field) && field != source_locale) {
+            let translation = match translation.as_str() {
+                Some(t) if !is_strings_dict(t) => t,
+                _ => continue,
+            };
+            let translations = term.entry(locale.clone()).or_insert_with(HashMap::new);
+            let usage = translations.entry(translation.to_owned()).or_insert_with(Usage::default);
+            usage.count += 1;
+            if usage.examples.len() < MAX_EXAMPLES {
+                usage.examples.push(occurrence.clone());
+            }
+        }
+    }
+
+    /// Finds the source texts translated in more than one way in a locale, with the most severe ones first.
+    pub fn inconsistencies(&self) -> Vec<Inconsistency> {
+        let mut inconsistencies = Vec::new();
+        for (&(ref source_locale, ref source), term) in &self.terms {
+            for (locale, translations) in term {
+                if translations.len() < 2 {
+                    continue;
+                }
+                let mut translations = translations.iter().map(|(t, u)| (&**t, u)).collect::<Vec<_>>();
+                translations.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+                inconsistencies.push(Inconsistency {
+                    source_locale: source_locale,
+                    source: source,
+                    locale: locale,
+                    total: translations.iter().map(|&(_, u)| u.count).sum(),
+                    translations: translations,
+                });
+            }
+        }
+        inconsistencies.sort_by(|a, b| {
+            b.deviations().cmp(&a.deviations())
+                .then_with(|| b.total.cmp(&a.total))
+                .then_with(|| (a.source, a.locale).cmp(&(b.source, b.locale)))
+        });
+        inconsistencies
+    }
+}
+
+/// A source text translated in more than one way in a locale.
+#[derive(Debug)]
+pub struct Inconsistency<'a> {
+    pub source_locale: &'a str,
+    pub source: &'a str,
+    pub locale: &'a str,
+    /// Number of entries translating the source text in this locale.
+    pub total: usize,
+    /// The distinct translations, the most common one first.
+    pub translations: Vec<(&'a str, &'a Usage)>,
+}
+
+impl<'a> Inconsistency<'a> {
+    /// Number of entries not using the most common translation.
+    pub fn deviations(&self) -> usize {
+        self.total - self.translations[0].1.count
+    }
+}
+
+#[test]
+fn test_inconsistencies() {
+    let mut terminology = Terminology::default();
+    let documents = [
+        ("com.apple.Preferences", "Cancel", "Abbrechen", "Annuler"),
+        ("com.apple.mobilesafari", "Cancel", "Abbrechen", "Annuler"),
+        ("com.apple.MobileSMS", "Cancel", "Abbruch", "Annuler"),
+        ("com.apple.springboard", "Done", "Fertig", "OK"),
+        ("com.apple.mobilemail", "Done", "Fertig", "Terminé"),
+    ];
+    for &(bundle_id, en, de, fr) in &documents {
+        terminology.add_document(&json!({
+            "BUNDLE_ID": bundle_id, "FILE": "Localizable.strings", "KEY": en.to_uppercase(), "SOURCE_LOCALE": "en",
+            "en": en, "de_DE": de, "fr_FR": fr,
+        }));
+    }
+
+    let inconsistencies = terminology.inconsistencies();
+    let summary = inconsistencies.iter().map(|i| {
+        let translations = i.translations.iter().map(|&(t, u)| (t, u.count)).collect::<Vec<_>>();
+        (i.source, i.locale, i.deviations(), translations)
+    }).collect::<Vec<_>>();
+    assert_eq!(summary, vec![
+        ("Cancel", "de_DE", 1, vec![("Abbrechen", 2), ("Abbruch", 1)]),
+        ("Done", "fr_FR", 1, vec![("OK", 1), ("Terminé", 1)]),
+    ]);
+    assert_eq!(inconsistencies[0].translations[1].1.examples[0].bundle_id, "com.apple.MobileSMS");
+}
+
+/// Formats the examples of a translation as `bundle_id/file:key`, separated by `; `.
+fn examples_to_string(usage: &Usage) -> String {
+    let examples = usage.examples.iter().map(|o| format!("{}/{}:{}", o.bundle_id, o.file, o.key)).collect::<Vec<_>>();
+    examples.join("; ")
+}
+
+/// Converts the inconsistencies into JSON.
+pub fn to_json(inconsistencies: &[Inconsistency]) -> Value {
+    let items = inconsistencies.iter().map(|i| {
+        let translations = i.translations.iter().map(|&(text, usage)| {
+            let examples = usage.examples.iter().map(|o| json!({
+                "bundle_id": o.bundle_id,
+                "file": o.file,
+                "key": o.key,
+            })).collect::<Vec<_>>();
+            json!({"text": text, "count": usage.count, "examples": examples})
+        }).collect::<Vec<_>>();
+        json!({
+            "source_locale": i.source_locale,
+            "source": i.source,
+            "locale": i.locale,
+            "total": i.total,
+            "deviations": i.deviations(),
+            "translations": translations,
+        })
+    }).collect::<Vec<_>>();
+    Value::Array(items)
+}
+
+/// Converts the inconsistencies into CSV, with one line per distinct translation and the columns
+/// `source_locale,source,locale,deviations,translation,count,examples`.
+pub fn to_csv(inconsistencies: &[Inconsistency]) -> String {
+    let mut csv = String::from("source_locale,source,locale,deviations,translation,count,examples\r\n");
+    for i in inconsistencies {
+        let deviations = i.deviations().to_string();
+        for &(text, usage) in &i.translations {
+            let count = usage.count.to_string();
+            let examples = examples_to_string(usage);
+            let fields = [i.source_locale, i.source, i.locale, &deviations, text, &count, &examples];
+            let fields = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+    }
+    csv
+}
+
+/// Converts the inconsistencies into plain text.
+pub fn to_text(inconsistencies: &[Inconsistency]) -> String {
+    let mut text = String::new();
+    for i in inconsistencies {
+        text.push_str(&format!("{:?} in {} ({} of {} differ)\n", i.source, i.locale, i.deviations(), i.total));
+        for &(translation, usage) in &i.translations {
+            text.push_str(&format!("    {:>6}  {:?}  {}\n", usage.count, translation, examples_to_string(usage)));
+        }
+    }
+    text
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
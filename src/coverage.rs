@@ -0,0 +1,228 @@
+//! Translation coverage of bundles.
+//!
+//! Every key having a translation in the development language (`SOURCE_LOCALE`) is expected to be translated into all
+//! other locales of the bundle. A key is *missing* in a locale without any translation, and *identical* when the
+//! translation is the same as the source text, which usually means it was never translated. Identical texts are not
+//! counted for locales of the same language as the source (e.g. `en_GB` of an `en_US` bundle), nor for texts without
+//! letters like `%@`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use serde_json::Value;
+
+use locales::is_locale_field;
+
+/// Identifies a key within a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileKey {
+    pub file: String,
+    pub key: String,
+}
+
+/// A key of the development language, with the locales it is translated into.
+#[derive(Debug)]
+struct SourceEntry {
+    key: FileKey,
+    translated: BTreeSet<String>,
+    identical: BTreeSet<String>,
+}
+
+/// Collects the keys of every bundle.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    bundles: BTreeMap<String, Vec<SourceEntry>>,
+    locales: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Returns the language part of a locale ID, e.g. `en` for `en_US`.
+fn language(locale: &str) -> &str {
+    locale.split('_').next().unwrap_or(locale)
+}
+
+impl Coverage {
+    /// Adds an indexed document. Bundles are identified by the `BUNDLE_ID`, or the `BUNDLE` path if missing.
+    pub fn add_document(&mut self, document: &Value) {
+        let bundle = match document["BUNDLE_ID"].as_str().or_else(|| document["BUNDLE"].as_str()) {
+            Some(b) => b,
+            None => return,
+        };
+        let source_locale = document["SOURCE_LOCALE"].as_str().unwrap_or("");
+        let fields = document.as_object().into_iter().flat_map(|d| d.iter());
+        let translations = fields.filter(|&(field, _)| is_locale_field(field) && field != source_locale);
+        let translations = translations.filter_map(|(locale, value)| value.as_str().map(|v| (locale, v)));
+
+        let locales = self.locales.entry(bundle.to_owned()).or_insert_with(BTreeSet::new);
+        let source = match document[source_locale].as_str() {
+            Some(s) => s,
+            None => {
+                locales.extend(translations.map(|(locale, _)| locale.clone()));
+                return;
+            },
+        };
+        let can_be_identical = source.chars().any(char::is_alphabetic);
+
+        let mut entry = SourceEntry {
+            key: FileKey {
+                file: document["FILE"].as_str().unwrap_or("").to_owned(),
+                key: document["KEY"].as_str().unwrap_or("").to_owned(),
+            },
+            translated: BTreeSet::new(),
+            identical: BTreeSet::new(),
+        };
+        for (locale, text) in translations {
+            locales.insert(locale.clone());
+            if can_be_identical && text == source && language(locale) != language(source_locale) {
+                entry.identical.insert(locale.clone());
+            } else {
+                entry.translated.insert(locale.clone());
+            }
+        }
+        self.bundles.entry(bundle.to_owned()).or_insert_with(Vec::new).push(entry);
+    }
+
+    /// Computes the coverage of each locale of every bundle.
+    pub fn reports(&self) -> Vec<BundleReport> {
+        self.bundles.iter().map(|(bundle, entries)| {
+            let locales = self.locales[bundle].iter().map(|locale| {
+                let mut report = LocaleReport {
+                    locale: locale,
+                    total: entries.len(),
+                    missing: Vec::new(),
+                    identical: Vec::new(),
+                };
+                for entry in entries {
+                    if entry.identical.contains(locale) {
+                        report.identical.push(&entry.key);
+                    } else if !entry.translated.contains(locale) {
+                        report.missing.push(&entry.key);
+                    }
+                }
+                report.missing.sort();
+                report.identical.sort();
+                report
+            }).collect();
+            BundleReport {
+                bundle: bundle,
+                total: entries.len(),
+                locales: locales,
+            }
+        }).collect()
+    }
+}
+
+/// The coverage of all locales of a bundle.
+#[derive(Debug)]
+pub struct BundleReport<'a> {
+    pub bundle: &'a str,
+    /// Number of keys in the development language.
+    pub total: usize,
+    pub locales: Vec<LocaleReport<'a>>,
+}
+
+/// The coverage of a locale in a bundle.
+#[derive(Debug)]
+pub struct LocaleReport<'a> {
+    pub locale: &'a str,
+    /// Number of keys in the development language.
+    pub total: usize,
+    pub missing: Vec<&'a FileKey>,
+    pub identical: Vec<&'a FileKey>,
+}
+
+impl<'a> LocaleReport<'a> {
+    /// Number of keys which are really translated.
+    pub fn translated(&self) -> usize {
+        self.total - self.missing.len() - self.identical.len()
+    }
+
+    /// Percentage of keys which are really translated. A bundle without any keys is fully covered.
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.translated() as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+#[test]
+fn test_coverage() {
+    let mut coverage = Coverage::default();
+    coverage.add_document(&json!({
+        "BUNDLE_ID": "com.apple.springboard", "SOURCE_LOCALE": "en_US", "FILE": "SpringBoard.strings", "KEY": "UNLOCK",
+        "en_US": "slide to unlock", "en_GB": "slide to unlock", "de_DE": "Entsperren", "fr_FR": "slide to unlock",
+    }));
+    coverage.add_document(&json!({
+        "BUNDLE_ID": "com.apple.springboard", "SOURCE_LOCALE": "en_US", "FILE": "SpringBoard.strings", "KEY": "COUNT",
+        "en_US": "%@", "fr_FR": "%@",
+    }));
+    coverage.add_document(&json!({
+        "BUNDLE_ID": "com.apple.springboard", "SOURCE_LOCALE": "en_US", "FILE": "SpringBoard.strings", "KEY": "STALE",
+        "ja_JP": "古い",
+    }));
+
+    let reports = coverage.reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].total, 2);
+    let summary = reports[0].locales.iter().map(|r| {
+        let keys = |keys: &[&FileKey]| keys.iter().map(|k| k.key.clone()).collect::<Vec<_>>();
+        (r.locale, r.translated(), keys(&r.missing), keys(&r.identical))
+    }).collect::<Vec<_>>();
+    assert_eq!(summary, vec![
+        ("de_DE", 1, vec!["COUNT".to_owned()], vec![]),
+        ("en_GB", 1, vec!["COUNT".to_owned()], vec![]),
+        ("fr_FR", 1, vec![], vec!["UNLOCK".to_owned()]),
+        ("ja_JP", 0, vec!["COUNT".to_owned(), "UNLOCK".to_owned()], vec![]),
+    ]);
+    assert_eq!(reports[0].locales[0].percentage(), 50.0);
+}
+
+/// Converts the keys into JSON.
+fn keys_to_json(keys: &[&FileKey]) -> Value {
+    Value::Array(keys.iter().map(|k| json!({"file": k.file, "key": k.key})).collect())
+}
+
+/// Converts the reports into JSON. The lists of missing and identical keys are included if `with_keys` is true,
+/// otherwise only their numbers are given.
+pub fn to_json(reports: &[BundleReport], with_keys: bool) -> Value {
+    let bundles = reports.iter().map(|bundle| {
+        let locales = bundle.locales.iter().map(|report| {
+            let mut locale = json!({
+                "locale": report.locale,
+                "coverage": report.percentage(),
+                "translated": report.translated(),
+                "missing": report.missing.len(),
+                "identical": report.identical.len(),
+            });
+            if with_keys {
+                locale["missingKeys"] = keys_to_json(&report.missing);
+                locale["identicalKeys"] = keys_to_json(&report.identical);
+            }
+            locale
+        }).collect::<Vec<_>>();
+        json!({
+            "bundle": bundle.bundle,
+            "total": bundle.total,
+            "locales": locales,
+        })
+    }).collect::<Vec<_>>();
+    Value::Array(bundles)
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
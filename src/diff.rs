@@ -0,0 +1,356 @@
+//! Compare the translations of two builds.
+//!
+//! A translation is identified across builds by the bundle identifier, the file and the key, since the bundle paths
+//! usually differ between builds (e.g. the mount point of the root filesystem contains the build number). Entries are
+//! compared locale by locale, so the report tells that the key `AWAY_LOCK_LABEL` of `SpringBoard.strings` was changed
+//! in `fr_FR` but not in `de_DE`.
+
+use std::borrow::Cow;
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use serde_json::Value;
+
+use locales::is_locale_field;
+
+/// Identifies a translation across builds.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EntryKey {
+    /// The `BUNDLE_ID` field, e.g. `com.apple.springboard`.
+    pub bundle_id: String,
+    /// The `FILE` field, e.g. `SpringBoard.strings`.
+    pub file: String,
+    /// The `KEY` field, e.g. `AWAY_LOCK_LABEL`.
+    pub key: String,
+}
+
+/// All translations of a build, keyed by the entry and then the locale ID.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    entries: BTreeMap<EntryKey, BTreeMap<String, String>>,
+}
+
+impl Snapshot {
+    /// Adds an indexed document. Documents without a `BUNDLE_ID` field are identified by the `BUNDLE` path instead.
+    pub fn add_document(&mut self, document: &Value) {
+        let bundle_id = document["BUNDLE_ID"].as_str().or_else(|| document["BUNDLE"].as_str());
+        let (bundle_id, file, key) = match (bundle_id, document["FILE"].as_str(), document["KEY"].as_str()) {
+            (Some(b), Some(f), Some(k)) => (b, f, k),
+            _ => return,
+        };
+
+        let fields = document.as_object().into_iter().flat_map(|d| d.iter());
+        let translations = fields.filter(|&(field, _)| is_locale_field(field)).map(|(locale, value)| {
+            let text = match *value {
+                Value::String(ref s) => s.clone(),
+                ref v => v.to_string(), // *.stringsdict entries.
+            };
+            (locale.clone(), text)
+        });
+
+        let entry_key = EntryKey {
+            bundle_id: bundle_id.to_owned(),
+            file: file.to_owned(),
+            key: key.to_owned(),
+        };
+        self.entries.entry(entry_key).or_insert_with(BTreeMap::new).extend(translations);
+    }
+}
+
+/// How a translation changed between two builds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// The translation only exists in the new build.
+    Added,
+    /// The translation only exists in the old build.
+    Removed,
+    /// The translation exists in both builds with different texts.
+    Changed,
+}
+
+const ALL_CHANGE_KINDS: &[ChangeKind] = &[ChangeKind::Added, ChangeKind::Removed, ChangeKind::Changed];
+
+impl ChangeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// A translation of a locale which differs between two builds.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change<'a> {
+    pub entry: &'a EntryKey,
+    pub locale: &'a str,
+    pub kind: ChangeKind,
+    /// The translation in the old build.
+    pub old: Option<&'a str>,
+    /// The translation in the new build.
+    pub new: Option<&'a str>,
+}
+
+/// Compares two snapshots, listing the changes ordered by the entry and then the locale.
+pub fn diff<'a>(old: &'a Snapshot, new: &'a Snapshot) -> Vec<Change<'a>> {
+    let entry_keys = old.entries.keys().chain(new.entries.keys()).collect::<BTreeSet<_>>();
+    let mut changes = Vec::new();
+
+    for entry in entry_keys {
+        let old_translations = old.entries.get(entry);
+        let new_translations = new.entries.get(entry);
+        let locales = old_translations.into_iter().chain(new_translations).flat_map(|t| t.keys());
+        for locale in locales.map(|l| &**l).collect::<BTreeSet<_>>() {
+            let old_text = old_translations.and_then(|t| t.get(locale)).map(|s| &**s);
+            let new_text = new_translations.and_then(|t| t.get(locale)).map(|s| &**s);
+            let kind = match (old_text, new_text) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Removed,
+                (Some(o), Some(n)) if o != n => ChangeKind::Changed,
+                _ => continue,
+            };
+            changes.push(Change {
+                entry: entry,
+                locale: locale,
+                kind: kind,
+                old: old_text,
+                new: new_text,
+            });
+        }
+    }
+
+    changes
+}
+
+#[test]
+fn test_diff() {
+    let mut old = Snapshot::default();
+    old.add_document(&json!({
+        "BUNDLE_ID": "com.apple.springboard", "FILE": "SpringBoard.strings", "KEY": "AWAY_LOCK_LABEL",
+        "en_US": "slide to unlock", "fr_FR": "déverrouiller", "de_DE": "Entsperren", "it_IT": "sblocca",
+    }));
+    let mut new = Snapshot::default();
+    new.add_document(&json!({
+        "BUNDLE_ID": "com.apple.springboard", "FILE": "SpringBoard.strings", "KEY": "AWAY_LOCK_LABEL",
+        "en_US": "slide to unlock", "fr_FR": "Déverrouiller", "de_DE": "Entsperren", "ja_JP": "ロック解除",
+    }));
+
+    let changes = diff(&old, &new);
+    let summary = changes.iter().map(|c| (c.locale, c.kind, c.old, c.new)).collect::<Vec<_>>();
+    assert_eq!(summary, vec![
+        ("fr_FR", ChangeKind::Changed, Some("déverrouiller"), Some("Déverrouiller")),
+        ("it_IT", ChangeKind::Removed, Some("sblocca"), None),
+        ("ja_JP", ChangeKind::Added, None, Some("ロック解除")),
+    ]);
+    assert_eq!(changes[0].entry.key, "AWAY_LOCK_LABEL");
+}
+
+/// Converts the changes into a JSON report, with the number of changes of each kind per locale in `summary`.
+pub fn to_json(changes: &[Change]) -> Value {
+    let mut summary = BTreeMap::new();
+    for change in changes {
+        let counts = summary.entry(change.locale).or_insert_with(|| {
+            ALL_CHANGE_KINDS.iter().map(|k| (k.name(), 0)).collect::<BTreeMap<_, _>>()
+        });
+        *counts.get_mut(change.kind.name()).unwrap() += 1;
+    }
+
+    let changes = changes.iter().map(|change| json!({
+        "change": change.kind.name(),
+        "locale": change.locale,
+        "bundle_id": change.entry.bundle_id,
+        "file": change.entry.file,
+        "key": change.entry.key,
+        "old": change.old,
+        "new": change.new,
+    })).collect::<Vec<_>>();
+
+    json!({
+        "summary": summary,
+        "changes": changes,
+    })
+}
+
+/// Quotes a CSV field if needed.
+pub fn csv_field(field: &str) -> Cow<str> {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Converts the changes into CSV, with the columns `change,locale,bundle_id,file,key,old,new`.
+pub fn to_csv(changes: &[Change]) -> String {
+    let mut csv = String::from("change,locale,bundle_id,file,key,old,new\r\n");
+    for change in changes {
+        let fields = [
+            change.kind.name(),
+            change.locale,
+            &change.entry.bundle_id,
+            &change.entry.file,
+            &change.entry.key,
+            change.old.unwrap_or(""),
+            change.new.unwrap_or(""),
+        ];
+        let fields = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+#[test]
+fn test_to_csv() {
+    let entry = EntryKey {
+        bundle_id: "com.apple.Preferences".to_owned(),
+        file: "General.strings".to_owned(),
+        key: "ABOUT".to_owned(),
+    };
+    let changes = [Change {
+        entry: &entry,
+        locale: "en_US",
+        kind: ChangeKind::Changed,
+        old: Some("About"),
+        new: Some("About \"This\", iPhone"),
+    }];
+    assert_eq!(to_csv(&changes), "change,locale,bundle_id,file,key,old,new\r\n\
+                                  changed,en_US,com.apple.Preferences,General.strings,ABOUT,About,\
+                                  \"About \"\"This\"\", iPhone\"\r\n");
+}
+
+/// A part of a character-level diff between two texts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextEdit<'a> {
+    /// Text common to both.
+    Equal(&'a str),
+    /// Text only in the old text.
+    Delete(&'a str),
+    /// Text only in the new text.
+    Insert(&'a str),
+}
+
+/// Texts with more character pairs than this in their differing parts are not diffed character by character, to keep
+/// the table of common subsequence lengths small.
+const MAX_TEXT_DIFF_CELLS: usize = 4_000_000;
+
+/// Finds the character-level differences between two texts, using the longest common subsequence.
+pub fn diff_text<'a>(old: &'a str, new: &'a str) -> Vec<TextEdit<'a>> {
+    let prefix_len = old.chars().zip(new.chars())
+        .take_while(|&(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+    let (old_rest, new_rest) = (&old[prefix_len..], &new[prefix_len..]);
+    let suffix_len = old_rest.chars().rev().zip(new_rest.chars().rev())
+        .take_while(|&(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+    let old_mid = &old_rest[..old_rest.len() - suffix_len];
+    let new_mid = &new_rest[..new_rest.len() - suffix_len];
+
+    let mut edits = Vec::new();
+    if prefix_len > 0 {
+        edits.push(TextEdit::Equal(&old[..prefix_len]));
+    }
+
+    let old_chars = old_mid.char_indices().collect::<Vec<_>>();
+    let new_chars = new_mid.char_indices().collect::<Vec<_>>();
+    let (n, m) = (old_chars.len(), new_chars.len());
+    if n * m > MAX_TEXT_DIFF_CELLS {
+        edits.push(TextEdit::Delete(old_mid));
+        edits.push(TextEdit::Insert(new_mid));
+    } else {
+        // lcs[i * (m + 1) + j] is the length of the longest common subsequence of old_chars[i..] and new_chars[j..].
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if old_chars[i].1 == new_chars[j].1 {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    max(lcs[(i + 1) * (m + 1) + j], lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+
+        // Each run is (kind, start, end), where the byte offsets are in old_mid for Equal and Delete, and in new_mid
+        // for Insert.
+        let mut runs: Vec<(u8, usize, usize)> = Vec::new();
+        {
+            let mut push = |kind: u8, start: usize, c: char| {
+                let end = start + c.len_utf8();
+                match runs.last_mut() {
+                    Some(run) if run.0 == kind && run.2 == start => run.2 = end,
+                    _ => runs.push((kind, start, end)),
+                }
+            };
+            let (mut i, mut j) = (0, 0);
+            while i < n || j < m {
+                if i < n && j < m && old_chars[i].1 == new_chars[j].1 {
+                    push(b'=', old_chars[i].0, old_chars[i].1);
+                    i += 1;
+                    j += 1;
+                } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                    push(b'-', old_chars[i].0, old_chars[i].1);
+                    i += 1;
+                } else {
+                    push(b'+', new_chars[j].0, new_chars[j].1);
+                    j += 1;
+                }
+            }
+        }
+        edits.extend(runs.into_iter().map(|(kind, start, end)| match kind {
+            b'=' => TextEdit::Equal(&old_mid[start..end]),
+            b'-' => TextEdit::Delete(&old_mid[start..end]),
+            _ => TextEdit::Insert(&new_mid[start..end]),
+        }));
+    }
+
+    if suffix_len > 0 {
+        edits.push(TextEdit::Equal(&old_rest[old_rest.len() - suffix_len..]));
+    }
+    edits.retain(|e| match *e {
+        TextEdit::Equal(s) | TextEdit::Delete(s) | TextEdit::Insert(s) => !s.is_empty(),
+    });
+    edits
+}
+
+#[test]
+fn test_diff_text() {
+    assert_eq!(diff_text("Entsperren", "Entsperren"), vec![TextEdit::Equal("Entsperren")]);
+    assert_eq!(diff_text("déverrouiller", "Déverrouiller"), vec![
+        TextEdit::Delete("d"),
+        TextEdit::Insert("D"),
+        TextEdit::Equal("éverrouiller"),
+    ]);
+    assert_eq!(diff_text("slide to unlock", "slide up to unlock"), vec![
+        TextEdit::Equal("slide "),
+        TextEdit::Insert("up "),
+        TextEdit::Equal("to unlock"),
+    ]);
+    assert_eq!(diff_text("Edit %@", "Edit “%@”"), vec![
+        TextEdit::Equal("Edit "),
+        TextEdit::Insert("“"),
+        TextEdit::Equal("%@"),
+        TextEdit::Insert("”"),
+    ]);
+    assert_eq!(diff_text("", "ロック解除"), vec![TextEdit::Insert("ロック解除")]);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
@@ -9,9 +9,15 @@ error_chain! {
         Plist(::plist::Error);
         Hyper(::hyper::error::Error);
         Json(::serde_json::Error);
+        Xml(::xml::reader::Error);
+        Toml(::toml::de::Error);
     }
 
     errors {
+        ReadLocaleMap(path: String) {
+            description("cannot read locale map")
+            display("cannot read locale map {}", path)
+        }
         CreateIndex {
             description("cannot create index")
         }
@@ -19,6 +25,18 @@ error_chain! {
             description("cannot create index")
             display("cannot create index, unexpected reply from Elasticsearch: {}", reply)
         }
+        UnexpectedReply(reply: Value) {
+            description("unexpected reply from Elasticsearch")
+            display("unexpected reply from Elasticsearch: {}", reply)
+        }
+        ReadBuild(build: String) {
+            description("cannot read translations of build")
+            display("cannot read translations of build {}", build)
+        }
+        FormatMismatches(count: usize) {
+            description("format specifiers of translations do not match the source")
+            display("found {} format specifier mismatches", count)
+        }
         ReadLproj(lproj: PathBuf) {
             description("cannot read *.lproj")
             display("cannot read {}", lproj.display())
@@ -26,6 +44,31 @@ error_chain! {
         InvalidPlist {
             description("*.strings file is not a valid plist")
         }
+        ReadResourceFile(path: PathBuf) {
+            description("cannot read Windows resource file")
+            display("cannot read {}", path.display())
+        }
+        InvalidStrings(line: usize) {
+            description("invalid *.strings syntax")
+            display("invalid *.strings syntax at line {}", line)
+        }
+        MissingTargetLanguage {
+            description("XLIFF file does not specify the target-language, use --locale to provide one")
+        }
+        InvalidLocaleCount {
+            description("*.strings files need exactly one --locale, *.xcstrings files at least one")
+        }
+        UnsupportedCatalog(path: String) {
+            description("unsupported translation file")
+            display("unsupported translation file {}, expecting *.strings, *.xcstrings or *.xliff", path)
+        }
+        InvalidMui {
+            description("*.mui file is not a valid PE image with resources")
+        }
+        InvalidInterfaceFile(path: PathBuf) {
+            description("not a compiled interface file")
+            display("{} is not a compiled *.nib or *.storyboardc", path.display())
+        }
         IndexTranslations(bundle: PathBuf) {
             description("cannot index translations")
             display("cannot index translations for bundle {}", bundle.display())
@@ -7,6 +7,12 @@ use hyper::header::ContentType;
 use serde_json::{to_vec, to_writer, from_reader, Value};
 
 use error::{ErrorKind, Result, ResultExt};
+use scroll::scroll;
+
+/// Maximum number of characters of a string kept in the `keyword` subfield of each field, which the exact, prefix and
+/// regex searches are matched against. Lucene rejects terms longer than 32766 bytes, i.e. 8191 characters of 4 bytes in
+/// UTF-8. (The default of dynamic mappings is only 256.)
+const KEYWORD_MAX_LENGTH: u32 = 8191;
 
 /// Elasticsearch client.
 pub struct Es<'a> {
@@ -38,6 +44,17 @@ impl<'a> Es<'a> {
                 self.type_: {
                     "_all": {"enabled": false},
                     "dynamic": true,
+                    "dynamic_templates": [{
+                        "strings": {
+                            "match_mapping_type": "string",
+                            "mapping": {
+                                "type": "text",
+                                "fields": {
+                                    "keyword": {"type": "keyword", "ignore_above": KEYWORD_MAX_LENGTH},
+                                },
+                            },
+                        },
+                    }],
                 },
             },
         })).unwrap();
@@ -95,6 +112,45 @@ impl<'a> Es<'a> {
         let items = result["items"].as_array();
         Ok(items.map(|a| a.iter().filter(|r| r["index"]["status"].as_i64() == Some(201)).count()).unwrap_or(0))
     }
+
+    /// Posts a JSON request to the `path` relative to the Elasticsearch URL, and returns the reply. Fails if the reply
+    /// is an error.
+    fn post_json(&self, path: &str, request: &Value) -> Result<Value> {
+        let body = to_vec(request).unwrap();
+        let resp = self.client
+            .post(self.base.join(path).unwrap())
+            .header(ContentType::json())
+            .body(Body::BufBody(&body, body.len()))
+            .send()?;
+        let content: Value = from_reader(resp)?;
+        if let Some(error) = content.get("error") {
+            bail!(ErrorKind::UnexpectedReply(error.clone()));
+        }
+        Ok(content)
+    }
+
+    /// Sends a search request, and returns the reply.
+    pub fn search(&self, request: &Value) -> Result<Value> {
+        self.post_json(&format!("{}/{}/_search", self.index, self.type_), request)
+    }
+
+    /// Counts the documents matching the query.
+    pub fn count(&self, query: &Value) -> Result<u64> {
+        let content = self.post_json(&format!("{}/{}/_count", self.index, self.type_), &json!({"query": query}))?;
+        Ok(content["count"].as_u64().unwrap_or(0))
+    }
+
+    /// Deletes every document matching the query. Returns the number of documents deleted.
+    pub fn delete_by_query(&self, query: &Value) -> Result<u64> {
+        let path = format!("{}/{}/_delete_by_query?conflicts=proceed", self.index, self.type_);
+        let content = self.post_json(&path, &json!({"query": query}))?;
+        Ok(content["deleted"].as_u64().unwrap_or(0))
+    }
+
+    /// Reads every document matching the query using the scroll API, and passes the `_source` of each to `f`.
+    pub fn scan<F: FnMut(Value)>(&self, query: Value, f: F) -> Result<()> {
+        scroll(self.index, self.type_, query, |path, request| self.post_json(path, request), f)
+    }
 }
 
 /*
@@ -0,0 +1,830 @@
+//! Resolve Interface Builder object IDs.
+//!
+//! `*.strings` files generated from storyboards and xibs use keys like `Xy3-aB-cD1.normalTitle`, where `Xy3-aB-cD1` is
+//! the ID of an object in the `*.storyboard` or `*.xib` source file. This module reads the interface file to find out
+//! the class and the original texts of every object, e.g. the object `Xy3-aB-cD1` is a `UIButton` whose `normalTitle`
+//! is "Sign In".
+//!
+//! Besides the XML source files, the compiled `*.nib` and `*.storyboardc` files shipped in system bundles are read.
+//! These are either `NIBArchive` files (iOS) or `NSKeyedArchiver` binary property lists (macOS). Their localizable
+//! texts are `NSLocalizableString` objects, which carry the `*.strings` key (`NSKey`) and the development text
+//! (`NSDev`); the class is taken from the object holding the string.
+
+use std::path::{Path, PathBuf};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::{File, read_dir};
+use std::io::{BufReader, Read};
+use xml::reader::{EventReader, XmlEvent};
+use xml::attribute::OwnedAttribute;
+
+use error::{Result, ErrorKind};
+
+/// Extensions of the interface files, in the order of preference. The source files are preferred since they also
+/// describe objects without localizable texts.
+pub const INTERFACE_EXTENSIONS: &[&str] = &["storyboard", "xib", "storyboardc", "nib"];
+
+/// Attributes of an element which hold localizable texts.
+const TEXT_ATTRIBUTES: &[&str] = &[
+    "text", "title", "placeholder", "placeholderString", "headerTitle", "footerTitle", "prompt", "backButtonTitle",
+    "label", "paletteLabel", "toolTip",
+];
+
+/// An object in an Interface Builder file.
+pub struct IbObject {
+    /// Class of the object, e.g. `UIButton`.
+    pub class: String,
+    /// Localizable texts of the object, keyed by the property name used in `*.strings` files, e.g. `normalTitle`.
+    pub texts: HashMap<String, String>,
+}
+
+fn find_attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|a| a.name.local_name == name && a.name.prefix.is_none()).map(|a| &*a.value)
+}
+
+/// Derives the class name from the element name, e.g. `<button>` becomes `UIButton` on iOS.
+fn class_name(class_prefix: &str, element: &str) -> String {
+    let mut chars = element.chars();
+    let first = chars.next().map(|c| c.to_ascii_uppercase());
+    format!("{}{}{}", class_prefix, first.into_iter().collect::<String>(), chars.as_str())
+}
+
+/// Reads all objects from a `*.storyboard`, `*.xib`, `*.nib` or `*.storyboardc` file, keyed by the object ID.
+pub fn read_interface_file(path: &Path) -> Result<HashMap<String, IbObject>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("nib") | Some("storyboardc") => read_compiled_interface_file(path),
+        _ => read_interface_source(BufReader::new(File::open(path)?)),
+    }
+}
+
+/// Reads all objects from the XML source of a `*.storyboard` or `*.xib` file.
+fn read_interface_source<R: Read>(input: R) -> Result<HashMap<String, IbObject>> {
+    let mut reader = EventReader::new(input);
+    let mut objects = HashMap::new();
+    let mut class_prefix = "UI";
+
+    // The ID of the nearest object enclosing each open element.
+    let mut stack: Vec<Option<String>> = Vec::new();
+    // The (object ID, property) of the `<string key="…">` element being read.
+    let mut string_property: Option<(String, String)> = None;
+
+    loop {
+        match reader.next()? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let element = &*name.local_name;
+                let parent = stack.last().cloned().and_then(|id| id);
+
+                if element == "document" {
+                    class_prefix = match find_attribute(&attributes, "targetRuntime") {
+                        Some(rt) if rt.starts_with("MacOSX") => "NS",
+                        Some(rt) if rt.starts_with("watchKit") => "WKInterface",
+                        _ => "UI",
+                    };
+                }
+
+                if let Some(id) = find_attribute(&attributes, "id") {
+                    let class = match find_attribute(&attributes, "customClass") {
+                        Some(c) => c.to_owned(),
+                        None => class_name(class_prefix, element),
+                    };
+                    let texts = TEXT_ATTRIBUTES.iter().filter_map(|&attr| {
+                        find_attribute(&attributes, attr).map(|text| (attr.to_owned(), text.to_owned()))
+                    }).collect();
+                    objects.insert(id.to_owned(), IbObject { class: class, texts: texts });
+                    stack.push(Some(id.to_owned()));
+                    continue;
+                }
+
+                if let Some(object) = parent.as_ref().and_then(|id| objects.get_mut(id)) {
+                    match element {
+                        // <state key="normal" title="…"/> in a button.
+                        "state" => {
+                            let key = find_attribute(&attributes, "key");
+                            if let (Some(key), Some(title)) = (key, find_attribute(&attributes, "title")) {
+                                object.texts.insert(format!("{}Title", key), title.to_owned());
+                            }
+                        },
+                        // <segment title="…"/> in a segmented control.
+                        "segment" => if let Some(title) = find_attribute(&attributes, "title") {
+                            let index = object.texts.keys().filter(|k| k.starts_with("segmentTitles[")).count();
+                            object.texts.insert(format!("segmentTitles[{}]", index), title.to_owned());
+                        },
+                        // <accessibility key="accessibilityConfiguration" label="…" hint="…"/>
+                        "accessibility" => {
+                            for &(attr, property) in &[("label", "accessibilityLabel"), ("hint", "accessibilityHint")] {
+                                if let Some(text) = find_attribute(&attributes, attr) {
+                                    object.texts.insert(property.to_owned(), text.to_owned());
+                                }
+                            }
+                        },
+                        // <string key="text">…</string> for multi-line texts.
+                        "string" => if let Some(key) = find_attribute(&attributes, "key") {
+                            if TEXT_ATTRIBUTES.contains(&key) {
+                                object.texts.insert(key.to_owned(), String::new());
+                                string_property = Some((parent.clone().unwrap(), key.to_owned()));
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+                stack.push(parent);
+            },
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => {
+                if let Some((ref id, ref property)) = string_property {
+                    if let Some(text) = objects.get_mut(id).and_then(|o| o.texts.get_mut(property)) {
+                        text.push_str(&s);
+                    }
+                }
+            },
+            XmlEvent::EndElement { .. } => {
+                stack.pop();
+                string_property = None;
+            },
+            XmlEvent::EndDocument => break,
+            _ => {},
+        }
+    }
+
+    Ok(objects)
+}
+
+#[test]
+fn test_read_interface_file() {
+    use std::env::temp_dir;
+    use std::fs::remove_file;
+    use std::io::Write;
+
+    let xib = r#"<?xml version="1.0" encoding="UTF-8"?>
+<document type="com.apple.InterfaceBuilder3.CocoaTouch.XIB" version="3.0" targetRuntime="iOS.CocoaTouch">
+    <objects>
+        <view contentMode="scaleToFill" id="iN0-l3-epB">
+            <subviews>
+                <button opaque="NO" id="Xy3-aB-cD1">
+                    <state key="normal" title="Sign In"/>
+                    <accessibility key="accessibilityConfiguration" label="Sign in to your account"/>
+                </button>
+                <label text="Welcome" id="kLm-12-nOp" customClass="GreetingLabel"/>
+                <textView id="qRs-34-tUv">
+                    <string key="text">First line
+Second line</string>
+                </textView>
+            </subviews>
+        </view>
+    </objects>
+</document>"#;
+    let path = temp_dir().join("lproj2es-test_read_interface_file.xib");
+    File::create(&path).unwrap().write_all(xib.as_bytes()).unwrap();
+    let objects = read_interface_file(&path);
+    remove_file(&path).unwrap();
+    let objects = objects.unwrap();
+
+    assert_eq!(objects["iN0-l3-epB"].class, "UIView");
+    assert!(objects["iN0-l3-epB"].texts.is_empty());
+    assert_eq!(objects["Xy3-aB-cD1"].class, "UIButton");
+    assert_eq!(objects["Xy3-aB-cD1"].texts, hashmap! {
+        "normalTitle".to_owned() => "Sign In".to_owned(),
+        "accessibilityLabel".to_owned() => "Sign in to your account".to_owned(),
+    });
+    assert_eq!(objects["kLm-12-nOp"].class, "GreetingLabel");
+    assert_eq!(objects["kLm-12-nOp"].texts["text"], "Welcome");
+    assert_eq!(objects["qRs-34-tUv"].class, "UITextView");
+    assert_eq!(objects["qRs-34-tUv"].texts["text"], "First line\nSecond line");
+}
+
+/// A value in a compiled interface file. Numbers, booleans and dates are not needed to resolve the texts, so they
+/// are all read as `Null`.
+#[derive(Debug, Clone, PartialEq)]
+enum ArchiveValue {
+    Null,
+    Data(Vec<u8>),
+    String(String),
+    /// Reference to another object, by its index in the archive (or in the binary property list).
+    Object(usize),
+    Array(Vec<ArchiveValue>),
+    Dictionary(HashMap<String, ArchiveValue>),
+}
+
+/// An object in a compiled interface file.
+#[derive(Debug, Clone, PartialEq)]
+struct ArchivedObject {
+    /// Class of the object, e.g. `UIButton`.
+    class: String,
+    /// The encoded fields, in order. `NIBArchive` files may repeat a key, e.g. for the elements of an array.
+    fields: Vec<(String, ArchiveValue)>,
+}
+
+impl ArchivedObject {
+    fn field(&self, key: &str) -> Option<&ArchiveValue> {
+        self.fields.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v)
+    }
+}
+
+/// Reads big-endian unsigned integers of up to 8 bytes.
+fn read_be(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |n, &b| n << 8 | u64::from(b))
+}
+
+/// Reads little-endian unsigned integers of up to 8 bytes.
+fn read_le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |n, &b| n << 8 | u64::from(b))
+}
+
+/// Reader of a binary property list (`bplist00`).
+struct BinaryPlist<'a> {
+    data: &'a [u8],
+    offset_size: usize,
+    ref_size: usize,
+    object_count: usize,
+    offset_table: usize,
+    /// Number of objects decoded so far. Shared objects are decoded once for every reference, so this is capped to
+    /// the size of the data, which is enough when only strings are shared, but stops a chain of arrays referencing
+    /// the next one twice from taking exponential time.
+    visited: Cell<usize>,
+}
+
+/// Maximum nesting of containers in a binary property list, which guards against reference cycles.
+const MAX_PLIST_DEPTH: u32 = 32;
+
+impl<'a> BinaryPlist<'a> {
+    /// Reads the top object of a binary property list. Returns `None` if the data is malformed.
+    fn read(data: &'a [u8]) -> Option<ArchiveValue> {
+        if !data.starts_with(b"bplist00") || data.len() < 40 {
+            return None;
+        }
+        let trailer = &data[data.len() - 32 ..];
+        let plist = BinaryPlist {
+            data: data,
+            offset_size: trailer[6] as usize,
+            ref_size: trailer[7] as usize,
+            object_count: read_be(&trailer[8 .. 16]) as usize,
+            offset_table: read_be(&trailer[24 .. 32]) as usize,
+            visited: Cell::new(0),
+        };
+        if plist.offset_size == 0 || plist.offset_size > 8 || plist.ref_size == 0 || plist.ref_size > 8 {
+            return None;
+        }
+        plist.object(read_be(&trailer[16 .. 24]) as usize, 0)
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Option<&'a [u8]> {
+        self.data.get(offset .. offset.checked_add(len)?)
+    }
+
+    /// Reads the length of an object with the given marker, returning the length and the offset of the content.
+    fn length(&self, marker: u8, offset: usize) -> Option<(usize, usize)> {
+        if marker & 0xf != 0xf {
+            return Some(((marker & 0xf) as usize, offset + 1));
+        }
+        let int_marker = *self.data.get(offset + 1)?;
+        if int_marker & 0xf0 != 0x10 || int_marker & 0xf > 3 {
+            return None;
+        }
+        let size = 1 << (int_marker & 0xf);
+        Some((read_be(self.bytes(offset + 2, size)?) as usize, offset + 2 + size))
+    }
+
+    fn refs(&self, offset: usize, count: usize) -> Option<Vec<usize>> {
+        let bytes = self.bytes(offset, count.checked_mul(self.ref_size)?)?;
+        Some(bytes.chunks(self.ref_size).map(|r| read_be(r) as usize).collect())
+    }
+
+    fn object(&self, index: usize, depth: u32) -> Option<ArchiveValue> {
+        let visited = self.visited.get() + 1;
+        if index >= self.object_count || depth > MAX_PLIST_DEPTH || visited > self.data.len() {
+            return None;
+        }
+        self.visited.set(visited);
+        let entry = self.offset_table.checked_add(index.checked_mul(self.offset_size)?)?;
+        let offset = read_be(self.bytes(entry, self.offset_size)?) as usize;
+        let marker = *self.data.get(offset)?;
+        Some(match marker >> 4 {
+            // null, booleans, integers, reals and dates.
+            0x0 | 0x1 | 0x2 | 0x3 => ArchiveValue::Null,
+            0x4 => {
+                let (len, start) = self.length(marker, offset)?;
+                ArchiveValue::Data(self.bytes(start, len)?.to_vec())
+            },
+            0x5 => {
+                let (len, start) = self.length(marker, offset)?;
+                ArchiveValue::String(String::from_utf8_lossy(self.bytes(start, len)?).into_owned())
+            },
+            0x6 => {
+                let (len, start) = self.length(marker, offset)?;
+                let bytes = self.bytes(start, len.checked_mul(2)?)?;
+                let units = bytes.chunks(2).map(|u| read_be(u) as u16).collect::<Vec<_>>();
+                ArchiveValue::String(String::from_utf16_lossy(&units))
+            },
+            0x8 => ArchiveValue::Object(read_be(self.bytes(offset + 1, (marker & 0xf) as usize + 1)?) as usize),
+            0xa => {
+                let (len, start) = self.length(marker, offset)?;
+                let refs = self.refs(start, len)?;
+                ArchiveValue::Array(refs.into_iter().map(|r| self.object(r, depth + 1)).collect::<Option<_>>()?)
+            },
+            0xd => {
+                let (len, start) = self.length(marker, offset)?;
+                let refs = self.refs(start, len.checked_mul(2)?)?;
+                let (keys, values) = refs.split_at(len);
+                let mut dict = HashMap::new();
+                for (&key, &value) in keys.iter().zip(values) {
+                    let key = match self.object(key, depth + 1)? {
+                        ArchiveValue::String(key) => key,
+                        _ => return None,
+                    };
+                    dict.insert(key, self.object(value, depth + 1)?);
+                }
+                ArchiveValue::Dictionary(dict)
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Extracts the objects of an `NSKeyedArchiver` archive. Plain strings in the archive become `NSString` objects with
+/// an `NS.string` field.
+fn keyed_archive_objects(archive: ArchiveValue) -> Option<Vec<ArchivedObject>> {
+    let mut archive = match archive {
+        ArchiveValue::Dictionary(archive) => archive,
+        _ => return None,
+    };
+    if archive.get("$archiver") != Some(&ArchiveValue::String("NSKeyedArchiver".to_owned())) {
+        return None;
+    }
+    let objects = match archive.remove("$objects") {
+        Some(ArchiveValue::Array(objects)) => objects,
+        _ => return None,
+    };
+
+    let class_name = |value: Option<&ArchiveValue>| match value {
+        Some(&ArchiveValue::Object(i)) => match objects.get(i) {
+            Some(&ArchiveValue::Dictionary(ref class)) => match class.get("$classname") {
+                Some(&ArchiveValue::String(ref name)) => name.clone(),
+                _ => String::new(),
+            },
+            _ => String::new(),
+        },
+        _ => String::new(),
+    };
+    Some(objects.iter().map(|object| match *object {
+        ArchiveValue::Dictionary(ref fields) => ArchivedObject {
+            class: class_name(fields.get("$class")),
+            fields: fields.iter().filter(|&(k, _)| k != "$class").map(|(k, v)| (k.clone(), v.clone())).collect(),
+        },
+        ArchiveValue::String(ref s) => ArchivedObject {
+            class: "NSString".to_owned(),
+            fields: vec![("NS.string".to_owned(), ArchiveValue::String(s.clone()))],
+        },
+        _ => ArchivedObject { class: String::new(), fields: Vec::new() },
+    }).collect())
+}
+
+/// Cursor over a `NIBArchive` file.
+struct NibCursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> NibCursor<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position .. self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    /// Reads a variable-length integer, stored in groups of 7 bits from the least significant, where the last byte has
+    /// the high bit set.
+    fn varint(&mut self) -> Option<usize> {
+        let mut result = 0;
+        for shift in (0 .. 5).map(|i| i * 7) {
+            let byte = self.bytes(1)?[0];
+            result |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 != 0 {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Extracts the objects of a `NIBArchive` file. Returns `None` if the data is malformed.
+fn nib_archive_objects(data: &[u8]) -> Option<Vec<ArchivedObject>> {
+    if !data.starts_with(b"NIBArchive") {
+        return None;
+    }
+    let header = data.get(10 .. 50)?.chunks(4).map(|n| read_le(n) as usize).collect::<Vec<_>>();
+    let (object_count, key_count, value_count, class_count) = (header[2], header[4], header[6], header[8]);
+    let cursor = |position| NibCursor { data: data, position: position };
+
+    let mut classes = Vec::new();
+    let mut c = cursor(header[9]);
+    for _ in 0 .. class_count {
+        let len = c.varint()?;
+        let extra_count = c.varint()?;
+        c.bytes(extra_count.checked_mul(4)?)?;
+        let name = c.bytes(len)?;
+        let name = name.split(|&b| b == 0).next().unwrap_or(name);
+        classes.push(String::from_utf8_lossy(name).into_owned());
+    }
+
+    let mut keys = Vec::new();
+    let mut c = cursor(header[5]);
+    for _ in 0 .. key_count {
+        let len = c.varint()?;
+        keys.push(String::from_utf8_lossy(c.bytes(len)?).into_owned());
+    }
+
+    let mut values = Vec::new();
+    let mut c = cursor(header[7]);
+    for _ in 0 .. value_count {
+        let key = keys.get(c.varint()?)?.clone();
+        let value = match c.bytes(1)?[0] {
+            0 => { c.bytes(1)?; ArchiveValue::Null },
+            1 => { c.bytes(2)?; ArchiveValue::Null },
+            2 | 6 => { c.bytes(4)?; ArchiveValue::Null },
+            3 | 7 => { c.bytes(8)?; ArchiveValue::Null },
+            4 | 5 | 9 => ArchiveValue::Null,
+            8 => {
+                let len = c.varint()?;
+                ArchiveValue::Data(c.bytes(len)?.to_vec())
+            },
+            10 => ArchiveValue::Object(read_le(c.bytes(4)?) as usize),
+            _ => return None,
+        };
+        values.push((key, value));
+    }
+
+    let mut objects = Vec::new();
+    let mut c = cursor(header[3]);
+    for _ in 0 .. object_count {
+        let class = classes.get(c.varint()?)?.clone();
+        let start = c.varint()?;
+        let count = c.varint()?;
+        let fields = values.get(start .. start.checked_add(count)?)?.to_vec();
+        objects.push(ArchivedObject { class: class, fields: fields });
+    }
+    Some(objects)
+}
+
+/// Reads a string value, either stored directly or as an `NSString` object.
+fn archived_string(objects: &[ArchivedObject], value: &ArchiveValue) -> Option<String> {
+    match *value {
+        ArchiveValue::String(ref s) => Some(s.clone()),
+        ArchiveValue::Data(ref bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        ArchiveValue::Object(i) => {
+            let object = objects.get(i)?;
+            let content = object.field("NS.string").or_else(|| object.field("NS.bytes"))?;
+            match *content {
+                ArchiveValue::Object(_) => None, // avoid following reference cycles.
+                ref content => archived_string(objects, content),
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Collects the indices of the objects referenced by a value.
+fn collect_references(value: &ArchiveValue, references: &mut Vec<usize>) {
+    match *value {
+        ArchiveValue::Object(i) => references.push(i),
+        ArchiveValue::Array(ref values) => for v in values {
+            collect_references(v, references);
+        },
+        ArchiveValue::Dictionary(ref values) => for v in values.values() {
+            collect_references(v, references);
+        },
+        _ => {},
+    }
+}
+
+/// Finds the objects with localizable texts in a compiled interface file.
+///
+/// The class of an object is the class of the object holding its `NSLocalizableString`, skipping collections and
+/// helper objects like `UIButtonContent`.
+fn compiled_objects(archived: &[ArchivedObject]) -> HashMap<String, IbObject> {
+    let mut owners = vec![None; archived.len()];
+    for (i, object) in archived.iter().enumerate() {
+        let mut references = Vec::new();
+        for &(_, ref value) in &object.fields {
+            collect_references(value, &mut references);
+        }
+        for r in references {
+            if r < owners.len() && owners[r].is_none() {
+                owners[r] = Some(i);
+            }
+        }
+    }
+
+    let is_helper = |class: &str| {
+        class.ends_with("Content") || class.starts_with("NSArray") || class.starts_with("NSMutableArray")
+            || class.starts_with("NSDictionary") || class.starts_with("NSMutableDictionary")
+    };
+
+    let mut objects = HashMap::new();
+    for (i, object) in archived.iter().enumerate().filter(|&(_, o)| o.class == "NSLocalizableString") {
+        let key = match object.field("NSKey").and_then(|v| archived_string(archived, v)) {
+            Some(key) => key,
+            None => continue,
+        };
+        let (id, property) = match key.find('.') {
+            Some(p) => (&key[.. p], &key[p+1 ..]),
+            None => continue,
+        };
+        let text = object.field("NSDev").and_then(|v| archived_string(archived, v)).unwrap_or_default();
+
+        let mut owner = owners[i];
+        for _ in 0 .. archived.len() {
+            match owner {
+                Some(o) if is_helper(&archived[o].class) => owner = owners[o],
+                _ => break,
+            }
+        }
+        let class = owner.map_or_else(String::new, |o| archived[o].class.clone());
+
+        objects.entry(id.to_owned())
+            .or_insert_with(|| IbObject { class: class, texts: HashMap::new() })
+            .texts.insert(property.to_owned(), text);
+    }
+    objects
+}
+
+/// Lists the archive files of a compiled interface file. `*.storyboardc` folders contain a `*.nib` for each scene,
+/// and a `*.nib` may itself be a folder of nibs for different OS versions.
+fn list_nib_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_file() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+    for entry in read_dir(path)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |e| e == "nib") || path.is_dir() {
+            list_nib_files(&path, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads all objects with localizable texts from a compiled `*.nib` or `*.storyboardc` file, keyed by the object ID.
+fn read_compiled_interface_file(path: &Path) -> Result<HashMap<String, IbObject>> {
+    let mut files = Vec::new();
+    list_nib_files(path, &mut files)?;
+
+    let mut objects = HashMap::new();
+    let mut archive_count = 0;
+    for file in files {
+        let mut data = Vec::new();
+        File::open(&file)?.read_to_end(&mut data)?;
+        // skip anything else in the folder, e.g. the `Info.plist` of storyboards or the XML `designable.nib`.
+        let archived = nib_archive_objects(&data).or_else(|| BinaryPlist::read(&data).and_then(keyed_archive_objects));
+        let archived = match archived {
+            Some(archived) => archived,
+            None => continue,
+        };
+        archive_count += 1;
+        for (id, IbObject { class, texts }) in compiled_objects(&archived) {
+            objects.entry(id).or_insert_with(|| IbObject { class: class, texts: HashMap::new() }).texts.extend(texts);
+        }
+    }
+
+    if archive_count == 0 {
+        bail!(ErrorKind::InvalidInterfaceFile(path.to_owned()));
+    }
+    Ok(objects)
+}
+
+#[test]
+fn test_nib_archive_objects() {
+    fn varint(mut n: usize, out: &mut Vec<u8>) {
+        while n >= 0x80 {
+            out.push((n & 0x7f) as u8);
+            n >>= 7;
+        }
+        out.push(n as u8 | 0x80);
+    }
+
+    // A `UILabel` with a text, and a `UIButton` whose title is held by a `UIButtonContent`.
+    let classes = ["UILabel", "NSLocalizableString", "NSString", "UIButton", "UIButtonContent"];
+    let keys = ["UIText", "NSKey", "NSDev", "NS.bytes", "UIButtonStatefulContent", "UITitle", "UIEnabled"];
+    let object = |i: u8| vec![10, i, 0, 0, 0];
+    let data = |s: &str| {
+        let mut value = vec![8];
+        varint(s.len(), &mut value);
+        value.extend(s.bytes());
+        value
+    };
+    let values = vec![
+        (0, object(1)),
+        (1, object(2)), (2, object(3)),
+        (3, data("kLm-12-nOp.text")),
+        (3, data("Welcome")),
+        (6, vec![4]), (4, object(5)),
+        (5, object(6)),
+        (1, object(7)), (2, object(8)),
+        (3, data("Xy3-aB-cD1.normalTitle")),
+        (3, data("Sign In")),
+    ];
+    let objects = [(0, 0, 1), (1, 1, 2), (2, 3, 1), (2, 4, 1), (3, 5, 2), (4, 7, 1), (1, 8, 2), (2, 10, 1), (2, 11, 1)];
+
+    let mut class_table = Vec::new();
+    for class in &classes {
+        varint(class.len() + 1, &mut class_table);
+        varint(0, &mut class_table);
+        class_table.extend(class.bytes().chain(Some(0)));
+    }
+    let mut key_table = Vec::new();
+    for key in &keys {
+        varint(key.len(), &mut key_table);
+        key_table.extend(key.bytes());
+    }
+    let mut value_table = Vec::new();
+    for &(key, ref value) in &values {
+        varint(key, &mut value_table);
+        value_table.extend(value);
+    }
+    let mut object_table = Vec::new();
+    for &(class, start, count) in &objects {
+        varint(class, &mut object_table);
+        varint(start, &mut object_table);
+        varint(count, &mut object_table);
+    }
+
+    let mut data = b"NIBArchive".to_vec();
+    let mut offset = 50;
+    let mut header = vec![1, 9];
+    for &(count, ref table) in &[
+        (objects.len(), &object_table), (keys.len(), &key_table), (values.len(), &value_table),
+        (classes.len(), &class_table),
+    ] {
+        header.extend(&[count, offset]);
+        offset += table.len();
+    }
+    for n in header {
+        data.extend((0 .. 4).map(|i| (n >> (i * 8)) as u8));
+    }
+    for table in &[object_table, key_table, value_table, class_table] {
+        data.extend(table);
+    }
+
+    let archived = nib_archive_objects(&data).unwrap();
+    assert_eq!(archived.len(), 9);
+    assert_eq!(archived[4].class, "UIButton");
+    assert_eq!(archived[4].field("UIButtonStatefulContent"), Some(&ArchiveValue::Object(5)));
+
+    let objects = compiled_objects(&archived);
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects["kLm-12-nOp"].class, "UILabel");
+    assert_eq!(objects["kLm-12-nOp"].texts["text"], "Welcome");
+    assert_eq!(objects["Xy3-aB-cD1"].class, "UIButton");
+    assert_eq!(objects["Xy3-aB-cD1"].texts["normalTitle"], "Sign In");
+
+    assert_eq!(nib_archive_objects(&data[.. data.len() - 3]), None);
+}
+
+#[test]
+fn test_keyed_archive_objects() {
+    /// Writes a binary property list, with one-byte references.
+    fn write_plist(value: &ArchiveValue) -> Vec<u8> {
+        fn write_length(marker: u8, len: usize, out: &mut Vec<u8>) {
+            if len < 15 {
+                out.push(marker | len as u8);
+            } else {
+                out.extend(&[marker | 0xf, 0x10, len as u8]);
+            }
+        }
+        fn write_object(value: &ArchiveValue, objects: &mut Vec<Vec<u8>>) -> u8 {
+            let index = objects.len();
+            objects.push(Vec::new());
+            let mut out = Vec::new();
+            match *value {
+                ArchiveValue::String(ref s) => {
+                    write_length(0x50, s.len(), &mut out);
+                    out.extend(s.bytes());
+                },
+                ArchiveValue::Object(uid) => out.extend(&[0x80, uid as u8]),
+                ArchiveValue::Array(ref values) => {
+                    write_length(0xa0, values.len(), &mut out);
+                    let refs = values.iter().map(|v| write_object(v, objects)).collect::<Vec<_>>();
+                    out.extend(refs);
+                },
+                ArchiveValue::Dictionary(ref values) => {
+                    write_length(0xd0, values.len(), &mut out);
+                    let mut keys = Vec::new();
+                    let mut refs = Vec::new();
+                    for (k, v) in values {
+                        keys.push(write_object(&ArchiveValue::String(k.clone()), objects));
+                        refs.push(write_object(v, objects));
+                    }
+                    out.extend(keys);
+                    out.extend(refs);
+                },
+                _ => out.push(0),
+            }
+            objects[index] = out;
+            index as u8
+        }
+
+        let mut objects = Vec::new();
+        write_object(value, &mut objects);
+        let mut data = b"bplist00".to_vec();
+        let mut offsets = Vec::new();
+        for object in objects {
+            offsets.push(data.len());
+            data.extend(object);
+        }
+        let offset_table = data.len();
+        for offset in &offsets {
+            data.extend(&[(offset >> 8) as u8, *offset as u8]);
+        }
+        data.extend(&[0, 0, 0, 0, 0, 0, 2, 1]);
+        for &n in &[offsets.len(), 0, offset_table] {
+            data.extend((0 .. 8).rev().map(|i| (n >> (i * 8)) as u8));
+        }
+        data
+    }
+
+    let string = |s: &str| ArchiveValue::String(s.to_owned());
+    let class = |name: &str| ArchiveValue::Dictionary(hashmap! {
+        "$classname".to_owned() => string(name),
+        "$classes".to_owned() => ArchiveValue::Array(vec![string(name), string("NSObject")]),
+    });
+    let archive = ArchiveValue::Dictionary(hashmap! {
+        "$archiver".to_owned() => string("NSKeyedArchiver"),
+        "$top".to_owned() => ArchiveValue::Dictionary(hashmap! {
+            "IB.objectdata".to_owned() => ArchiveValue::Object(1),
+        }),
+        "$objects".to_owned() => ArchiveValue::Array(vec![
+            string("$null"),
+            ArchiveValue::Dictionary(hashmap! {
+                "$class".to_owned() => ArchiveValue::Object(5),
+                "NSContents".to_owned() => ArchiveValue::Object(2),
+            }),
+            ArchiveValue::Dictionary(hashmap! {
+                "$class".to_owned() => ArchiveValue::Object(6),
+                "NSKey".to_owned() => ArchiveValue::Object(3),
+                "NSDev".to_owned() => ArchiveValue::Object(4),
+            }),
+            string("AbC-dE-fGh.title"),
+            string("Open Recent"),
+            class("NSMenuItem"),
+            class("NSLocalizableString"),
+        ]),
+    });
+
+    let data = write_plist(&archive);
+    assert_eq!(BinaryPlist::read(&data), Some(archive.clone()));
+
+    let archived = keyed_archive_objects(archive).unwrap();
+    assert_eq!(archived[1].class, "NSMenuItem");
+    assert_eq!(archived[3].class, "NSString");
+    let objects = compiled_objects(&archived);
+    assert_eq!(objects["AbC-dE-fGh"].class, "NSMenuItem");
+    assert_eq!(objects["AbC-dE-fGh"].texts["title"], "Open Recent");
+
+    assert_eq!(BinaryPlist::read(&data[.. data.len() - 1]), None);
+}
+
+#[test]
+fn test_binary_plist_shared_objects() {
+    /// Writes a binary property list from the encoded objects, with one-byte offsets and references.
+    fn write_plist(objects: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"bplist00".to_vec();
+        let mut offsets = Vec::new();
+        for object in objects {
+            offsets.push(data.len() as u8);
+            data.extend(object);
+        }
+        let offset_table = data.len();
+        data.extend(offsets);
+        data.extend(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        for &n in &[objects.len(), 0, offset_table] {
+            data.extend((0 .. 8).rev().map(|i| (n >> (i * 8)) as u8));
+        }
+        data
+    }
+
+    let x = ArchiveValue::String("x".to_owned());
+    let data = write_plist(&[vec![0xa2, 1, 1], b"\x51x".to_vec()]);
+    assert_eq!(BinaryPlist::read(&data), Some(ArchiveValue::Array(vec![x.clone(), x])));
+
+    // Every array references the next one twice, which would expand to 2^30 strings.
+    let mut objects = (1 .. 31).map(|i| vec![0xa2, i, i]).collect::<Vec<_>>();
+    objects.push(b"\x51x".to_vec());
+    assert_eq!(BinaryPlist::read(&write_plist(&objects)), None);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
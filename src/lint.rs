@@ -0,0 +1,285 @@
+//! Lint translations against the indexed corpus.
+//!
+//! Each translation of our own app is compared with the corpus (e.g. the indexed translations of an iOS build). The
+//! corpus is searched for entries with the same source text, and if they mostly translate it in another way, the
+//! translation is reported with the dominant one as the suggestion. When no entry has exactly the same source text,
+//! the most relevant entries with similar source texts are used instead, and the confidence is scaled by how similar
+//! the source texts are.
+
+use std::mem::replace;
+use serde_json::Value;
+
+use diff::{diff_text, TextEdit};
+
+/// Number of most relevant corpus entries considered by a fuzzy lookup.
+const FUZZY_SAMPLE_SIZE: u32 = 20;
+
+/// Number of distinct translations fetched for each locale.
+const MAX_CANDIDATES: u32 = 5;
+
+/// Name of the aggregation finding the most common matched source text. Locale IDs never start with an uppercase
+/// letter, so this does not clash with the aggregations of the locales.
+const SOURCE_AGGREGATION: &str = "SOURCE";
+
+/// How the corpus entries were matched with the source text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Fuzzy,
+}
+
+impl MatchKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchKind::Exact => "exact",
+            MatchKind::Fuzzy => "fuzzy",
+        }
+    }
+}
+
+/// Constructs the search request looking up the translations of a source text in the corpus.
+pub fn lookup_query(source_locale: &str, source: &str, locales: &[&str], build: Option<&str>, kind: MatchKind)
+    -> Value
+{
+    let mut filters = Vec::new();
+    if let Some(build) = build {
+        filters.push(json!({"term": {"BUILD.keyword": build}}));
+    }
+    let (query, sample) = match kind {
+        MatchKind::Exact => {
+            let keyword_field = format!("{}.keyword", source_locale);
+            filters.push(json!({"term": {keyword_field: source}}));
+            (json!({"bool": {"filter": filters}}), json!({"filter": {"match_all": {}}}))
+        },
+        MatchKind::Fuzzy => {
+            let query = json!({
+                "bool": {
+                    "must": {
+                        "match": {
+                            source_locale: {"query": source, "fuzziness": "AUTO", "operator": "and"},
+                        },
+                    },
+                    "filter": filters,
+                },
+            });
+            (query, json!({"sampler": {"shard_size": FUZZY_SAMPLE_SIZE}}))
+        },
+    };
+
+    let mut aggregations = json!({
+        SOURCE_AGGREGATION: {"terms": {"field": format!("{}.keyword", source_locale), "size": 1}},
+    });
+    for locale in locales {
+        let field = format!("{}.keyword", locale);
+        aggregations[*locale] = json!({"terms": {"field": field, "size": MAX_CANDIDATES}});
+    }
+    let mut matches = sample;
+    matches["aggregations"] = aggregations;
+
+    json!({
+        "size": 0,
+        "query": query,
+        "aggregations": {"matches": matches},
+    })
+}
+
+/// The translations of a source text found in the corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup {
+    pub kind: MatchKind,
+    /// The most common source text of the matched entries.
+    pub matched_source: String,
+    /// Similarity between the source text and the matched one, from 0 to 1.
+    pub similarity: f64,
+    /// The reply of the search request, containing the translation counts of every locale.
+    reply: Value,
+}
+
+impl Lookup {
+    /// Parses the reply of a `lookup_query`. Returns `None` if no entries matched.
+    pub fn from_reply(kind: MatchKind, source: &str, mut reply: Value) -> Option<Lookup> {
+        let matches = replace(&mut reply["aggregations"]["matches"], Value::Null);
+        let matched_source = matches[SOURCE_AGGREGATION]["buckets"][0]["key"].as_str()?.to_owned();
+        let similarity = similarity(source, &matched_source);
+        Some(Lookup {
+            kind: kind,
+            matched_source: matched_source,
+            similarity: similarity,
+            reply: matches,
+        })
+    }
+
+    /// Returns the distinct translations in a locale with their number of entries, the most common one first, and the
+    /// total number of entries translated into the locale.
+    pub fn candidates(&self, locale: &str) -> (Vec<(&str, u64)>, u64) {
+        let aggregation = &self.reply[locale];
+        let buckets = aggregation["buckets"].as_array().map_or(&[][..], |b| &**b);
+        let candidates = buckets.iter().filter_map(|b| Some((b["key"].as_str()?, b["doc_count"].as_u64()?)));
+        let candidates = candidates.collect::<Vec<_>>();
+        let other_count = aggregation["sum_other_doc_count"].as_u64().unwrap_or(0);
+        let total = candidates.iter().map(|&(_, count)| count).sum::<u64>() + other_count;
+        (candidates, total)
+    }
+
+    /// Compares a translation with the corpus. Returns the suggested translation and the confidence if the translation
+    /// is not the dominant one.
+    ///
+    /// The confidence is the share of the dominant translation minus the share of the given translation, scaled by
+    /// the similarity of the source texts.
+    pub fn suggest(&self, locale: &str, translation: &str) -> Option<(&str, u64, u64, f64)> {
+        let (candidates, total) = self.candidates(locale);
+        let &(suggestion, support) = candidates.first()?;
+        if suggestion == translation {
+            return None;
+        }
+        let own_count = candidates.iter().find(|&&(t, _)| t == translation).map_or(0, |&(_, count)| count);
+        let confidence = (support - own_count) as f64 / total as f64 * self.similarity;
+        Some((suggestion, support, total, confidence))
+    }
+}
+
+/// Computes how similar two texts are, as the ratio of characters in common.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count() + b.chars().count();
+    if len == 0 {
+        return 1.0;
+    }
+    let common = diff_text(a, b).into_iter().map(|edit| match edit {
+        TextEdit::Equal(s) => s.chars().count(),
+        _ => 0,
+    }).sum::<usize>();
+    (2 * common) as f64 / len as f64
+}
+
+#[test]
+fn test_lookup() {
+    let reply = json!({
+        "aggregations": {
+            "matches": {
+                "doc_count": 42,
+                "SOURCE": {"buckets": [{"key": "Cancel", "doc_count": 42}]},
+                "de_DE": {
+                    "sum_other_doc_count": 1,
+                    "buckets": [{"key": "Abbrechen", "doc_count": 38}, {"key": "Abbruch", "doc_count": 1}],
+                },
+                "fr_FR": {"sum_other_doc_count": 0, "buckets": [{"key": "Annuler", "doc_count": 40}]},
+            },
+        },
+    });
+    let lookup = Lookup::from_reply(MatchKind::Exact, "Cancel", reply).unwrap();
+    assert_eq!(lookup.similarity, 1.0);
+    assert_eq!(lookup.candidates("de_DE"), (vec![("Abbrechen", 38), ("Abbruch", 1)], 40));
+    assert_eq!(lookup.suggest("de_DE", "Abbruch"), Some(("Abbrechen", 38, 40, 0.925)));
+    assert_eq!(lookup.suggest("fr_FR", "Annuler"), None);
+    assert_eq!(lookup.suggest("ja_JP", "キャンセル"), None);
+
+    let empty = json!({"aggregations": {"matches": {"doc_count": 0, "SOURCE": {"buckets": []}}}});
+    assert_eq!(Lookup::from_reply(MatchKind::Fuzzy, "Cancel", empty), None);
+    assert_eq!(similarity("Cancel", "Cancel…"), 12.0 / 13.0);
+}
+
+/// A translation which differs from the dominant translation in the corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Path of the `*.strings` file containing the translation.
+    pub path: String,
+    pub key: String,
+    pub locale: String,
+    pub source: String,
+    pub translation: String,
+    pub suggestion: String,
+    pub confidence: f64,
+    /// Number of corpus entries using the suggestion.
+    pub support: u64,
+    /// Number of corpus entries translating the source text into the locale.
+    pub total: u64,
+    pub kind: MatchKind,
+    /// The source text of the corpus entries, which differs from `source` for fuzzy matches.
+    pub matched_source: String,
+}
+
+/// ID of the only SARIF rule.
+const RULE_ID: &str = "non-native-translation";
+
+/// Findings with at least this confidence are reported as SARIF warnings, the rest are notes.
+const WARNING_CONFIDENCE: f64 = 0.8;
+
+/// Converts a finding into JSON.
+fn finding_to_json(finding: &Finding) -> Value {
+    json!({
+        "path": finding.path,
+        "key": finding.key,
+        "locale": finding.locale,
+        "source": finding.source,
+        "translation": finding.translation,
+        "suggestion": finding.suggestion,
+        "confidence": finding.confidence,
+        "support": finding.support,
+        "total": finding.total,
+        "match": finding.kind.name(),
+        "matched_source": finding.matched_source,
+    })
+}
+
+/// Converts the findings into JSON.
+pub fn to_json(findings: &[Finding]) -> Value {
+    Value::Array(findings.iter().map(finding_to_json).collect())
+}
+
+/// Converts the findings into a SARIF 2.1.0 log, which can be uploaded to code scanning services.
+pub fn to_sarif(findings: &[Finding]) -> Value {
+    let results = findings.iter().map(|finding| {
+        let message = format!(
+            "{} translation of {:?} is {:?}, but {} of {} entries in the corpus use {:?} (confidence {:.2})",
+            finding.locale, finding.source, finding.translation, finding.support, finding.total, finding.suggestion,
+            finding.confidence,
+        );
+        json!({
+            "ruleId": RULE_ID,
+            "level": if finding.confidence >= WARNING_CONFIDENCE { "warning" } else { "note" },
+            "message": {"text": message},
+            "locations": [{
+                "physicalLocation": {"artifactLocation": {"uri": finding.path}},
+                "logicalLocations": [{"name": finding.key, "kind": "member"}],
+            }],
+            "properties": finding_to_json(finding),
+        })
+    }).collect::<Vec<_>>();
+
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": RULE_ID,
+                        "shortDescription": {"text": "Translation differs from the dominant translation in the corpus"},
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
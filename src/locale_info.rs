@@ -0,0 +1,491 @@
+//! Information about locale IDs shown by the search server.
+//!
+//! When a bundle lacks a translation for a locale, `fallback_locale_ids` gives the locales Apple would try instead,
+//! e.g. `en_AU` falls back to `en_GB` then `en_US`.
+//!
+//! `locale_info` describes a locale ID for display, with its English and native names, the text direction and the
+//! plural categories, e.g. `ar_SA` is "Arabic (Saudi Arabia)", written right-to-left, with six plural categories.
+
+use std::collections::HashMap;
+
+use locales::{Locale, LIKELY_SUBTAGS, LIKELY_SCRIPTS};
+
+// Sets of CLDR cardinal plural categories.
+const OTHER: &[&str] = &["other"];
+const ONE_OTHER: &[&str] = &["one", "other"];
+const ZERO_ONE_OTHER: &[&str] = &["zero", "one", "other"];
+const ONE_TWO_OTHER: &[&str] = &["one", "two", "other"];
+const ONE_FEW_OTHER: &[&str] = &["one", "few", "other"];
+const ONE_TWO_FEW_OTHER: &[&str] = &["one", "two", "few", "other"];
+const ONE_TWO_MANY_OTHER: &[&str] = &["one", "two", "many", "other"];
+const ONE_FEW_MANY_OTHER: &[&str] = &["one", "few", "many", "other"];
+const ONE_TWO_FEW_MANY_OTHER: &[&str] = &["one", "two", "few", "many", "other"];
+const ZERO_ONE_TWO_FEW_MANY_OTHER: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// Scripts written from right to left.
+const RTL_SCRIPTS: &[&str] = &["Adlm", "Arab", "Hebr", "Mand", "Nkoo", "Rohg", "Samr", "Syrc", "Thaa"];
+
+lazy_static! {
+    /// Parent locales which differ from the default locale of the language, mostly following CLDR's `parentLocales`.
+    static ref PARENT_LOCALES: HashMap<&'static str, &'static str> = hashmap!{
+        "en_AU" => "en_GB", "en_BE" => "en_GB", "en_HK" => "en_GB", "en_IE" => "en_GB", "en_IN" => "en_GB",
+        "en_MT" => "en_GB", "en_MY" => "en_GB", "en_NG" => "en_GB", "en_NZ" => "en_GB", "en_PK" => "en_GB",
+        "en_SG" => "en_GB", "en_ZA" => "en_GB",
+
+        "es_AR" => "es_419", "es_BO" => "es_419", "es_CL" => "es_419", "es_CO" => "es_419", "es_CR" => "es_419",
+        "es_CU" => "es_419", "es_DO" => "es_419", "es_EC" => "es_419", "es_GT" => "es_419", "es_HN" => "es_419",
+        "es_MX" => "es_419", "es_NI" => "es_419", "es_PA" => "es_419", "es_PE" => "es_419", "es_PR" => "es_419",
+        "es_PY" => "es_419", "es_SV" => "es_419", "es_US" => "es_419", "es_UY" => "es_419", "es_VE" => "es_419",
+
+        "pt_AO" => "pt_PT", "pt_CV" => "pt_PT", "pt_GW" => "pt_PT", "pt_MO" => "pt_PT", "pt_MZ" => "pt_PT",
+        "pt_ST" => "pt_PT", "pt_TL" => "pt_PT",
+
+        "zh_MO" => "zh_HK",
+        "zh_HK" => "zh_TW",
+
+        "nn_NO" => "nb_NO",
+    };
+
+    /// English name, native name and cardinal plural categories of each language, following CLDR.
+    static ref LANGUAGE_INFO: HashMap<&'static str, (&'static str, &'static str, &'static [&'static str])> = hashmap!{
+        "aa" => ("Afar", "Qafar", OTHER),
+        "af" => ("Afrikaans", "Afrikaans", ONE_OTHER),
+        "ak" => ("Akan", "Akan", ONE_OTHER),
+        "am" => ("Amharic", "አማርኛ", ONE_OTHER),
+        "an" => ("Aragonese", "aragonés", ONE_OTHER),
+        "ar" => ("Arabic", "العربية", ZERO_ONE_TWO_FEW_MANY_OTHER),
+        "as" => ("Assamese", "অসমীয়া", ONE_OTHER),
+        "ay" => ("Aymara", "Aymar aru", OTHER),
+        "az" => ("Azerbaijani", "azərbaycan", ONE_OTHER),
+
+        "be" => ("Belarusian", "беларуская", ONE_FEW_MANY_OTHER),
+        "bg" => ("Bulgarian", "български", ONE_OTHER),
+        "bi" => ("Bislama", "Bislama", OTHER),
+        "bm" => ("Bambara", "bamanakan", OTHER),
+        "bn" => ("Bangla", "বাংলা", ONE_OTHER),
+        "bo" => ("Tibetan", "བོད་སྐད་", OTHER),
+        "br" => ("Breton", "brezhoneg", ONE_TWO_FEW_MANY_OTHER),
+        "bs" => ("Bosnian", "bosanski", ONE_FEW_OTHER),
+
+        "ca" => ("Catalan", "català", ONE_OTHER),
+        "ce" => ("Chechen", "нохчийн", ONE_OTHER),
+        "cs" => ("Czech", "čeština", ONE_FEW_MANY_OTHER),
+        "cv" => ("Chuvash", "чӑваш", OTHER),
+        "cy" => ("Welsh", "Cymraeg", ZERO_ONE_TWO_FEW_MANY_OTHER),
+
+        "da" => ("Danish", "dansk", ONE_OTHER),
+        "de" => ("German", "Deutsch", ONE_OTHER),
+        "dv" => ("Divehi", "ދިވެހިބަސް", ONE_OTHER),
+        "dz" => ("Dzongkha", "རྫོང་ཁ", OTHER),
+
+        "el" => ("Greek", "Ελληνικά", ONE_OTHER),
+        "en" => ("English", "English", ONE_OTHER),
+        "eo" => ("Esperanto", "esperanto", ONE_OTHER),
+        "es" => ("Spanish", "español", ONE_OTHER),
+        "et" => ("Estonian", "eesti", ONE_OTHER),
+        "eu" => ("Basque", "euskara", ONE_OTHER),
+
+        "fa" => ("Persian", "فارسی", ONE_OTHER),
+        "ff" => ("Fulah", "Pulaar", ONE_OTHER),
+        "fi" => ("Finnish", "suomi", ONE_OTHER),
+        "fo" => ("Faroese", "føroyskt", ONE_OTHER),
+        "fr" => ("French", "français", ONE_OTHER),
+        "fy" => ("Western Frisian", "Frysk", ONE_OTHER),
+
+        "ga" => ("Irish", "Gaeilge", ONE_TWO_FEW_MANY_OTHER),
+        "gd" => ("Scottish Gaelic", "Gàidhlig", ONE_TWO_FEW_OTHER),
+        "gl" => ("Galician", "galego", ONE_OTHER),
+        "gn" => ("Guarani", "avañe'ẽ", OTHER),
+        "gu" => ("Gujarati", "ગુજરાતી", ONE_OTHER),
+        "gv" => ("Manx", "Gaelg", ONE_TWO_FEW_MANY_OTHER),
+
+        "ha" => ("Hausa", "Hausa", ONE_OTHER),
+        "he" => ("Hebrew", "עברית", ONE_TWO_MANY_OTHER),
+        "hi" => ("Hindi", "हिन्दी", ONE_OTHER),
+        "hr" => ("Croatian", "hrvatski", ONE_FEW_OTHER),
+        "ht" => ("Haitian Creole", "kreyòl ayisyen", OTHER),
+        "hu" => ("Hungarian", "magyar", ONE_OTHER),
+        "hy" => ("Armenian", "հայերեն", ONE_OTHER),
+
+        "ia" => ("Interlingua", "interlingua", ONE_OTHER),
+        "id" => ("Indonesian", "Indonesia", OTHER),
+        "ig" => ("Igbo", "Asụsụ Igbo", OTHER),
+        "ik" => ("Inupiaq", "Iñupiatun", OTHER),
+        "is" => ("Icelandic", "íslenska", ONE_OTHER),
+        "it" => ("Italian", "italiano", ONE_OTHER),
+        "iu" => ("Inuktitut", "ᐃᓄᒃᑎᑐᑦ", ONE_TWO_OTHER),
+
+        "ja" => ("Japanese", "日本語", OTHER),
+        "jv" => ("Javanese", "Jawa", OTHER),
+
+        "ka" => ("Georgian", "ქართული", ONE_OTHER),
+        "kk" => ("Kazakh", "қазақ тілі", ONE_OTHER),
+        "kl" => ("Kalaallisut", "kalaallisut", ONE_OTHER),
+        "km" => ("Khmer", "ខ្មែរ", OTHER),
+        "kn" => ("Kannada", "ಕನ್ನಡ", ONE_OTHER),
+        "ko" => ("Korean", "한국어", OTHER),
+        "ks" => ("Kashmiri", "کٲشُر", ONE_OTHER),
+        "ku" => ("Kurdish", "kurdî", ONE_OTHER),
+        "kw" => ("Cornish", "kernewek", ONE_TWO_OTHER),
+        "ky" => ("Kyrgyz", "кыргызча", ONE_OTHER),
+
+        "la" => ("Latin", "Latina", ONE_OTHER),
+        "lb" => ("Luxembourgish", "Lëtzebuergesch", ONE_OTHER),
+        "lg" => ("Ganda", "Luganda", ONE_OTHER),
+        "li" => ("Limburgish", "Limburgs", ONE_OTHER),
+        "ln" => ("Lingala", "lingála", ONE_OTHER),
+        "lo" => ("Lao", "ລາວ", OTHER),
+        "lt" => ("Lithuanian", "lietuvių", ONE_FEW_MANY_OTHER),
+        "lv" => ("Latvian", "latviešu", ZERO_ONE_OTHER),
+
+        "mg" => ("Malagasy", "Malagasy", ONE_OTHER),
+        "mh" => ("Marshallese", "Kajin M̧ajeļ", OTHER),
+        "mi" => ("Maori", "Māori", OTHER),
+        "mk" => ("Macedonian", "македонски", ONE_OTHER),
+        "ml" => ("Malayalam", "മലയാളം", ONE_OTHER),
+        "mn" => ("Mongolian", "монгол", ONE_OTHER),
+        "mr" => ("Marathi", "मराठी", ONE_OTHER),
+        "ms" => ("Malay", "Melayu", OTHER),
+        "mt" => ("Maltese", "Malti", ONE_FEW_MANY_OTHER),
+        "my" => ("Burmese", "မြန်မာ", OTHER),
+
+        "nb" => ("Norwegian Bokmål", "norsk bokmål", ONE_OTHER),
+        "ne" => ("Nepali", "नेपाली", ONE_OTHER),
+        "nl" => ("Dutch", "Nederlands", ONE_OTHER),
+        "nn" => ("Norwegian Nynorsk", "nynorsk", ONE_OTHER),
+        "nr" => ("South Ndebele", "isiNdebele", ONE_OTHER),
+        "ny" => ("Nyanja", "Chichewa", ONE_OTHER),
+
+        "oc" => ("Occitan", "occitan", ONE_OTHER),
+        "om" => ("Oromo", "Oromoo", ONE_OTHER),
+        "or" => ("Odia", "ଓଡ଼ିଆ", ONE_OTHER),
+        "os" => ("Ossetic", "ирон", ONE_OTHER),
+
+        "pa" => ("Punjabi", "ਪੰਜਾਬੀ", ONE_OTHER),
+        "pl" => ("Polish", "polski", ONE_FEW_MANY_OTHER),
+        "ps" => ("Pashto", "پښتو", ONE_OTHER),
+        "pt" => ("Portuguese", "português", ONE_OTHER),
+
+        "qu" => ("Quechua", "Runasimi", OTHER),
+
+        "rn" => ("Rundi", "Ikirundi", OTHER),
+        "ro" => ("Romanian", "română", ONE_FEW_OTHER),
+        "ru" => ("Russian", "русский", ONE_FEW_MANY_OTHER),
+        "rw" => ("Kinyarwanda", "Kinyarwanda", OTHER),
+
+        "sa" => ("Sanskrit", "संस्कृत भाषा", OTHER),
+        "sc" => ("Sardinian", "sardu", ONE_OTHER),
+        "sd" => ("Sindhi", "سنڌي", ONE_OTHER),
+        "se" => ("Northern Sami", "davvisámegiella", ONE_TWO_OTHER),
+        "si" => ("Sinhala", "සිංහල", ONE_OTHER),
+        "sk" => ("Slovak", "slovenčina", ONE_FEW_MANY_OTHER),
+        "sl" => ("Slovenian", "slovenščina", ONE_TWO_FEW_OTHER),
+        "so" => ("Somali", "Soomaali", ONE_OTHER),
+        "sq" => ("Albanian", "shqip", ONE_OTHER),
+        "sr" => ("Serbian", "српски", ONE_FEW_OTHER),
+        "ss" => ("Swati", "siSwati", ONE_OTHER),
+        "st" => ("Southern Sotho", "Sesotho", ONE_OTHER),
+        "su" => ("Sundanese", "Basa Sunda", OTHER),
+        "sv" => ("Swedish", "svenska", ONE_OTHER),
+        "sw" => ("Swahili", "Kiswahili", ONE_OTHER),
+
+        "ta" => ("Tamil", "தமிழ்", ONE_OTHER),
+        "te" => ("Telugu", "తెలుగు", ONE_OTHER),
+        "tg" => ("Tajik", "тоҷикӣ", OTHER),
+        "th" => ("Thai", "ไทย", OTHER),
+        "ti" => ("Tigrinya", "ትግርኛ", ONE_OTHER),
+        "tk" => ("Turkmen", "türkmen dili", ONE_OTHER),
+        "tn" => ("Tswana", "Setswana", ONE_OTHER),
+        "to" => ("Tongan", "lea fakatonga", OTHER),
+        "tr" => ("Turkish", "Türkçe", ONE_OTHER),
+        "ts" => ("Tsonga", "Xitsonga", ONE_OTHER),
+        "tt" => ("Tatar", "татар", OTHER),
+
+        "ug" => ("Uyghur", "ئۇيغۇرچە", ONE_OTHER),
+        "uk" => ("Ukrainian", "українська", ONE_FEW_MANY_OTHER),
+        "ur" => ("Urdu", "اردو", ONE_OTHER),
+        "uz" => ("Uzbek", "o‘zbek", ONE_OTHER),
+
+        "ve" => ("Venda", "Tshivenḓa", ONE_OTHER),
+        "vi" => ("Vietnamese", "Tiếng Việt", OTHER),
+
+        "wa" => ("Walloon", "walon", ONE_OTHER),
+        "wo" => ("Wolof", "Wolof", OTHER),
+
+        "xh" => ("Xhosa", "isiXhosa", ONE_OTHER),
+
+        "yi" => ("Yiddish", "ייִדיש", ONE_OTHER),
+        "yo" => ("Yoruba", "Èdè Yorùbá", OTHER),
+
+        "zh" => ("Chinese", "中文", OTHER),
+        "zu" => ("Zulu", "isiZulu", ONE_OTHER),
+
+        "agr" => ("Aguaruna", "Awajún", OTHER),
+        "anp" => ("Angika", "अंगिका", OTHER),
+        "ast" => ("Asturian", "asturianu", ONE_OTHER),
+        "ayc" => ("Southern Aymara", "Aymar aru", OTHER),
+        "bem" => ("Bemba", "Ichibemba", ONE_OTHER),
+        "ber" => ("Berber", "Tamaziɣt", OTHER),
+        "bhb" => ("Bhili", "भीली", OTHER),
+        "bho" => ("Bhojpuri", "भोजपुरी", ONE_OTHER),
+        "brx" => ("Bodo", "बड़ो", ONE_OTHER),
+        "byn" => ("Blin", "ብሊን", OTHER),
+        "chr" => ("Cherokee", "ᏣᎳᎩ", ONE_OTHER),
+        "crh" => ("Crimean Tatar", "qırımtatar", OTHER),
+        "csb" => ("Kashubian", "kaszëbsczi", OTHER),
+        "doi" => ("Dogri", "डोगरी", ONE_OTHER),
+        "fil" => ("Filipino", "Filipino", ONE_OTHER),
+        "fur" => ("Friulian", "furlan", ONE_OTHER),
+        "gez" => ("Geez", "ግዕዝ", OTHER),
+        "grc" => ("Ancient Greek", "Ἀρχαία Ἑλληνικὴ", OTHER),
+        "hak" => ("Hakka Chinese", "客家話", OTHER),
+        "hne" => ("Chhattisgarhi", "छत्तीसगढ़ी", OTHER),
+        "hsb" => ("Upper Sorbian", "hornjoserbšćina", ONE_TWO_FEW_OTHER),
+        "hus" => ("Huastec", "Tének", OTHER),
+        "kab" => ("Kabyle", "Taqbaylit", ONE_OTHER),
+        "kok" => ("Konkani", "कोंकणी", OTHER),
+        "lij" => ("Ligurian", "ligure", ONE_OTHER),
+        "lzh" => ("Literary Chinese", "文言", OTHER),
+        "mag" => ("Magahi", "मगही", OTHER),
+        "mai" => ("Maithili", "मैथिली", OTHER),
+        "mhr" => ("Eastern Mari", "марий", OTHER),
+        "miq" => ("Miskito", "Mískitu", OTHER),
+        "myv" => ("Erzya", "эрзянь", OTHER),
+        "nah" => ("Nahuatl", "Nāhuatl", ONE_OTHER),
+        "nan" => ("Min Nan Chinese", "閩南語", OTHER),
+        "nds" => ("Low German", "Plattdüütsch", ONE_OTHER),
+        "nhn" => ("Central Nahuatl", "Nāhuatl", ONE_OTHER),
+        "niu" => ("Niuean", "ko e vagahau Niuē", OTHER),
+        "nso" => ("Northern Sotho", "Sesotho sa Leboa", ONE_OTHER),
+        "pap" => ("Papiamento", "Papiamentu", ONE_OTHER),
+        "quy" => ("Ayacucho Quechua", "Runasimi", OTHER),
+        "quz" => ("Cusco Quechua", "Runasimi", OTHER),
+        "raj" => ("Rajasthani", "राजस्थानी", OTHER),
+        "sat" => ("Santali", "ᱥᱟᱱᱛᱟᱲᱤ", ONE_TWO_OTHER),
+        "sgs" => ("Samogitian", "žemaitėškai", ONE_FEW_MANY_OTHER),
+        "shs" => ("Shuswap", "Secwepemctsín", OTHER),
+        "sid" => ("Sidamo", "Sidaamu Afoo", OTHER),
+        "son" => ("Songhai", "Soŋay", OTHER),
+        "szl" => ("Silesian", "ślōnski", ONE_FEW_MANY_OTHER),
+        "tcy" => ("Tulu", "ತುಳು", OTHER),
+        "the" => ("Chitwania Tharu", "थारू", OTHER),
+        "tig" => ("Tigre", "ትግረ", ONE_OTHER),
+        "unm" => ("Unami", "Lënape", OTHER),
+        "wae" => ("Walser", "Walser", ONE_OTHER),
+        "wal" => ("Wolaytta", "ወላይታቱ", OTHER),
+        "yue" => ("Cantonese", "粵語", OTHER),
+    };
+
+    /// English names of scripts.
+    static ref SCRIPT_NAMES: HashMap<&'static str, &'static str> = hashmap!{
+        "Adlm" => "Adlam", "Arab" => "Arabic", "Armn" => "Armenian", "Beng" => "Bangla",
+        "Cans" => "Unified Canadian Aboriginal Syllabics", "Cher" => "Cherokee", "Cyrl" => "Cyrillic",
+        "Deva" => "Devanagari", "Ethi" => "Ethiopic", "Geor" => "Georgian", "Grek" => "Greek", "Gujr" => "Gujarati",
+        "Guru" => "Gurmukhi", "Hans" => "Simplified", "Hant" => "Traditional", "Hebr" => "Hebrew",
+        "Jpan" => "Japanese", "Khmr" => "Khmer", "Knda" => "Kannada", "Kore" => "Korean", "Laoo" => "Lao",
+        "Latn" => "Latin", "Mlym" => "Malayalam", "Mong" => "Mongolian", "Mymr" => "Myanmar", "Nkoo" => "N’Ko",
+        "Olck" => "Ol Chiki", "Orya" => "Odia", "Sinh" => "Sinhala", "Syrc" => "Syriac", "Taml" => "Tamil",
+        "Telu" => "Telugu", "Thaa" => "Thaana", "Thai" => "Thai", "Tibt" => "Tibetan",
+    };
+
+    /// English names of regions.
+    static ref REGION_NAMES: HashMap<&'static str, &'static str> = hashmap!{
+        "001" => "World", "150" => "Europe", "419" => "Latin America", "AE" => "United Arab Emirates",
+        "AF" => "Afghanistan", "AL" => "Albania", "AM" => "Armenia", "AO" => "Angola", "AR" => "Argentina",
+        "AT" => "Austria", "AU" => "Australia", "AW" => "Aruba", "AZ" => "Azerbaijan", "BA" => "Bosnia & Herzegovina",
+        "BD" => "Bangladesh", "BE" => "Belgium", "BG" => "Bulgaria", "BI" => "Burundi", "BO" => "Bolivia",
+        "BR" => "Brazil", "BT" => "Bhutan", "BY" => "Belarus", "CA" => "Canada", "CD" => "Congo - Kinshasa",
+        "CH" => "Switzerland", "CL" => "Chile", "CN" => "China", "CO" => "Colombia", "CR" => "Costa Rica",
+        "CU" => "Cuba", "CV" => "Cape Verde", "CZ" => "Czechia", "DE" => "Germany", "DK" => "Denmark",
+        "DO" => "Dominican Republic", "DZ" => "Algeria", "EC" => "Ecuador", "EE" => "Estonia", "EG" => "Egypt",
+        "ER" => "Eritrea", "ES" => "Spain", "ET" => "Ethiopia", "FI" => "Finland", "FO" => "Faroe Islands",
+        "FR" => "France", "GB" => "United Kingdom", "GE" => "Georgia", "GH" => "Ghana", "GL" => "Greenland",
+        "GR" => "Greece", "GT" => "Guatemala", "GW" => "Guinea-Bissau", "HK" => "Hong Kong", "HN" => "Honduras",
+        "HR" => "Croatia", "HT" => "Haiti", "HU" => "Hungary", "ID" => "Indonesia", "IE" => "Ireland",
+        "IL" => "Israel", "IN" => "India", "IQ" => "Iraq", "IR" => "Iran", "IS" => "Iceland", "IT" => "Italy",
+        "JP" => "Japan", "KE" => "Kenya", "KG" => "Kyrgyzstan", "KH" => "Cambodia", "KR" => "South Korea",
+        "KZ" => "Kazakhstan", "LA" => "Laos", "LI" => "Liechtenstein", "LK" => "Sri Lanka", "LT" => "Lithuania",
+        "LU" => "Luxembourg", "LV" => "Latvia", "MA" => "Morocco", "MD" => "Moldova", "ME" => "Montenegro",
+        "MG" => "Madagascar", "MH" => "Marshall Islands", "MK" => "Macedonia", "ML" => "Mali",
+        "MM" => "Myanmar (Burma)", "MN" => "Mongolia", "MO" => "Macau", "MT" => "Malta", "MV" => "Maldives",
+        "MW" => "Malawi", "MX" => "Mexico", "MY" => "Malaysia", "MZ" => "Mozambique", "NG" => "Nigeria",
+        "NI" => "Nicaragua", "NL" => "Netherlands", "NO" => "Norway", "NP" => "Nepal", "NU" => "Niue",
+        "NZ" => "New Zealand", "PA" => "Panama", "PE" => "Peru", "PH" => "Philippines", "PK" => "Pakistan",
+        "PL" => "Poland", "PR" => "Puerto Rico", "PT" => "Portugal", "PY" => "Paraguay", "RO" => "Romania",
+        "RS" => "Serbia", "RU" => "Russia", "RW" => "Rwanda", "SA" => "Saudi Arabia", "SE" => "Sweden",
+        "SG" => "Singapore", "SI" => "Slovenia", "SK" => "Slovakia", "SN" => "Senegal", "SO" => "Somalia",
+        "ST" => "São Tomé & Príncipe", "SV" => "El Salvador", "TH" => "Thailand", "TJ" => "Tajikistan",
+        "TL" => "Timor-Leste", "TM" => "Turkmenistan", "TO" => "Tonga", "TR" => "Turkey", "TV" => "Tuvalu",
+        "TW" => "Taiwan", "TZ" => "Tanzania", "UA" => "Ukraine", "UG" => "Uganda", "US" => "United States",
+        "UY" => "Uruguay", "UZ" => "Uzbekistan", "VE" => "Venezuela", "VN" => "Vietnam", "ZA" => "South Africa",
+        "ZM" => "Zambia",
+    };
+}
+
+/// Gets the likely script of a language, optionally in a region.
+fn likely_script(language: &str, region: Option<&str>) -> Option<&'static str> {
+    let in_region = region.and_then(|r| LIKELY_SCRIPTS.get(&*format!("{}_{}", language, r)).cloned());
+    in_region.or_else(|| LIKELY_SUBTAGS.get(language).map(|&(script, _)| script))
+}
+
+/// Gets the locale ID which a locale ID falls back to when a translation is missing, e.g. `en_AU` to `en_GB`.
+///
+/// Without a specific parent, a locale falls back to the default locale of its language and script, e.g. `de_AT` to
+/// `de_DE` and `zh_SG` to `zh_CN`. Returns `None` if the locale is already the default one.
+pub fn parent_locale_id(id: &str) -> Option<String> {
+    if let Some(parent) = PARENT_LOCALES.get(id) {
+        return Some(parent.to_string());
+    }
+
+    let mut locale = Locale::parse(id)?;
+    if !locale.variants.is_empty() {
+        locale.variants.clear();
+        return Some(locale.to_string());
+    }
+
+    let region = locale.region.take()?;
+    if locale.script.is_none() {
+        locale.script = likely_script(&locale.language, Some(&region)).map(str::to_owned);
+    }
+    let parent = locale.canonicalize().to_string();
+    if parent == id {
+        None
+    } else {
+        Some(parent)
+    }
+}
+
+/// Lists the chain of locale IDs a locale falls back to, e.g. `["es_419", "es_ES"]` for `es_MX`.
+pub fn fallback_locale_ids(id: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = id.to_owned();
+    while let Some(parent) = parent_locale_id(&current) {
+        if parent == id || result.contains(&parent) {
+            break;
+        }
+        result.push(parent.clone());
+        current = parent;
+    }
+    result
+}
+
+#[test]
+fn test_fallback_locale_ids() {
+    assert_eq!(fallback_locale_ids("en_AU"), vec!["en_GB", "en_US"]);
+    assert_eq!(fallback_locale_ids("en_US"), Vec::<String>::new());
+    assert_eq!(fallback_locale_ids("pt_PT"), vec!["pt_BR"]);
+    assert_eq!(fallback_locale_ids("es_MX"), vec!["es_419", "es_ES"]);
+    assert_eq!(fallback_locale_ids("de_AT"), vec!["de_DE"]);
+    assert_eq!(fallback_locale_ids("zh_MO"), vec!["zh_HK", "zh_TW"]);
+    assert_eq!(fallback_locale_ids("zh_SG"), vec!["zh_CN"]);
+    assert_eq!(fallback_locale_ids("sr_Latn_ME"), vec!["sr_Latn_RS"]);
+    assert_eq!(fallback_locale_ids("ca_ES_valencia"), vec!["ca_ES"]);
+    assert_eq!(fallback_locale_ids("Base"), Vec::<String>::new());
+}
+
+/// Display information of a locale ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleInfo {
+    /// The locale ID, e.g. `zh_HK`.
+    pub id: String,
+    /// The English display name, e.g. `Chinese (Hong Kong)`.
+    pub name: String,
+    /// The name of the language in itself, e.g. `中文`.
+    pub native_name: String,
+    /// The script the locale is likely written in, e.g. `Hant`.
+    pub script: Option<String>,
+    /// Whether the script is written from right to left.
+    pub rtl: bool,
+    /// The CLDR cardinal plural categories of the language, e.g. `["one", "other"]`. Empty if unknown.
+    pub plural_categories: &'static [&'static str],
+}
+
+/// Describes a locale ID for display. IDs which are not language tags (e.g. `"Base"`) or are of unknown languages are
+/// named by the ID itself.
+pub fn locale_info(id: &str) -> LocaleInfo {
+    let mut info = LocaleInfo {
+        id: id.to_owned(),
+        name: id.to_owned(),
+        native_name: id.to_owned(),
+        script: None,
+        rtl: false,
+        plural_categories: &[],
+    };
+
+    let locale = match Locale::parse(id) {
+        Some(locale) => locale,
+        None => return info,
+    };
+    let (english_name, native_name, plural_categories) = match LANGUAGE_INFO.get(&*locale.language) {
+        Some(&language_info) => language_info,
+        None => return info,
+    };
+
+    let mut qualifiers = Vec::new();
+    if let Some(ref script) = locale.script {
+        qualifiers.push(*SCRIPT_NAMES.get(&**script).unwrap_or(&&**script));
+    }
+    if let Some(ref region) = locale.region {
+        qualifiers.push(*REGION_NAMES.get(&**region).unwrap_or(&&**region));
+    }
+    qualifiers.extend(locale.variants.iter().map(|v| &**v));
+
+    let script = locale.script.clone().or_else(|| {
+        likely_script(&locale.language, locale.region.as_ref().map(|r| &**r)).map(str::to_owned)
+    });
+
+    info.name = if qualifiers.is_empty() {
+        english_name.to_owned()
+    } else {
+        format!("{} ({})", english_name, qualifiers.join(", "))
+    };
+    info.native_name = native_name.to_owned();
+    info.rtl = script.as_ref().map_or(false, |s| RTL_SCRIPTS.contains(&&**s));
+    info.script = script;
+    info.plural_categories = plural_categories;
+    info
+}
+
+#[test]
+fn test_locale_info() {
+    let info = locale_info("ar_SA");
+    assert_eq!(info.name, "Arabic (Saudi Arabia)");
+    assert_eq!(info.native_name, "العربية");
+    assert_eq!(info.script, Some("Arab".to_owned()));
+    assert!(info.rtl);
+    assert_eq!(info.plural_categories, &["zero", "one", "two", "few", "many", "other"]);
+
+    let info = locale_info("zh_Hans_HK");
+    assert_eq!(info.name, "Chinese (Simplified, Hong Kong)");
+    assert_eq!(info.script, Some("Hans".to_owned()));
+    assert!(!info.rtl);
+
+    assert_eq!(locale_info("zh_HK").script, Some("Hant".to_owned()));
+    assert_eq!(locale_info("eo_001").name, "Esperanto (World)");
+    assert_eq!(locale_info("ca_ES_valencia").name, "Catalan (Spain, valencia)");
+    assert!(locale_info("ur_PK").rtl);
+
+    let info = locale_info("Base");
+    assert_eq!(info.name, "Base");
+    assert_eq!(info.script, None);
+    assert!(info.plural_categories.is_empty());
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
\ No newline at end of file
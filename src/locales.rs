@@ -1,297 +1,319 @@
-//! Collection of well-known locale IDs.
+//! Parsing and canonicalization of locale tags.
 //!
-//! This module provides a single function `locale_id` that maps an `*.lproj` file name to a canonical locale ID.
-//! Aliases to the same locale, e.g. `English.lproj`, `en.lproj` and `en_US.lproj` are all mapped to the same output,
-//! `en_US`.
+//! A locale name is parsed as a BCP 47 language tag (language, script, region and variants), accepting both `-` and
+//! `_` as separators. Deprecated language and region codes are replaced, and the missing script and region are filled
+//! in from the likely-subtags table. The canonical ID is then written as `language_Script_REGION_variants`, where the
+//! script is omitted if it is the likely one for the language and region. So `zh-Hans` becomes `zh_CN`, `zh_Hant_HK`
+//! becomes `zh_HK`, and `sr-Latn` becomes `sr_Latn_RS`.
 //!
-//! This combination simplifies searching, because the user will only need to look for the `en_US` key, instead of all
-//! three variants.
-//!
-//! The default country of a language is referenced from these two links:
+//! The default script and country of a language is referenced from these links:
 //!
 //! * <https://lh.2xlibre.net/locales/>
 //! * <http://stackoverflow.com/questions/3040677/locale-codes-for-iphone-lproj-folders>
+//! * <http://www.unicode.org/cldr/charts/latest/supplemental/likely_subtags.html>
 
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
 
 lazy_static! {
-    // TODO: Switch to phf once it can run on stable without build.rs.
-    static ref LOCALE_MAP: HashMap<&'static str, &'static str> = hashmap!{
-        "aa" => "aa_ET",
-        "af" => "af_ZA", "Afrikaans" => "af_ZA",
-        "ak" => "ak_GH",
-        "am" => "am_ET", "Amharic" => "am_ET",
-        "an" => "an_ES",
-        "ar" => "ar_SA", "Arabic" => "ar_SA",
-        "as" => "as_IN", "Assamese" => "as_IN",
-        "ay" => "ay_PE", "Aymara" => "ay_PE",
-        "az" => "az_AZ", "Azerbaijani" => "az_AZ",
-
-        "be" => "be_BY", "Byelorussian" => "be_BY",
-        "bg" => "bg_BG", "Bulgarian" => "bg_BG",
-        "bi" => "bi_TV",
-        "bm" => "bm_ML",
-        "bn" => "bn_BD", "Bengali" => "bn_BD",
-        "bo" => "bo_CN", "Tibetan" => "bo_CN",
-        "br" => "br_FR", "Breton" => "br_FR",
-        "bs" => "bs_BA",
-
-        "ca" => "ca_ES", "Catalan" => "ca_ES",
-        "ce" => "ce_RU",
-        "cs" => "cs_CZ", "Czech" => "cs_CZ",
-        "cv" => "cv_RU",
-        "cy" => "cy_GB", "Welsh" => "cy_GB",
-
-        "da" => "da_DK", "Danish" => "da_DK",
-        "de" => "de_DE", "German" => "de_DE",
-        "dv" => "dv_MV",
-        "dz" => "dz_BT", "Dzongkha" => "dz_BT",
-
-        "el" => "el_GR", "Greek" => "el_GR",
-        "en" => "en_US", "English" => "en_US",
-        "eo" => "eo_001", "Esperanto" => "eo_001",
-        "es" => "es_ES", "Spanish" => "es_ES",
-        "et" => "et_EE", "Estonian" => "et_EE",
-        "eu" => "eu_ES", "Basque" => "eu_ES",
-
-        "fa" => "fa_IR", "Farsi" => "fa_IR",
-        "ff" => "ff_SN",
-        "fi" => "fi_FI", "Finnish" => "fi_FI",
-        "fo" => "fo_FO", "Faroese" => "fo_FO",
-        "fr" => "fr_FR", "French" => "fr_FR",
-        "fy" => "fy_DE",
-
-        "ga" => "ga_IE", "Irish" => "ga_IE",
-        "gd" => "gd_GB", "Scottish" => "gd_GB",
-        "gl" => "gl_ES", "Galician" => "gl_ES",
-        "gn" => "gn_PY", "Guarani" => "gn_PY",
-        "gu" => "gu_IN", "Gujarati" => "gu_IN",
-        "gv" => "gv_GB", "Manx" => "gv_GB",
-
-        "ha" => "ha_NG",
-        "he" => "he_IL", "Hebrew" => "he_IL",
-        "hi" => "hi_IN", "Hindi" => "hi_IN",
-        "hr" => "hr_HR", "Croatian" => "hr_HR",
-        "ht" => "ht_HT",
-        "hu" => "hu_HU", "Hungarian" => "hu_HU",
-        "hy" => "hy_AM", "Armenian" => "hy_AM",
-
-        "ia" => "ia_FR",
-        "id" => "id_ID", "Indonesian" => "id_ID",
-        "ig" => "ig_NG",
-        "ik" => "ik_CA",
-        "is" => "is_IS", "Icelandic" => "is_IS",
-        "it" => "it_IT", "Italian" => "it_IT",
-        "iu" => "iu_CA", "Inuktitut" => "iu_CA",
-
-        "ja" => "ja_JP", "Japanese" => "ja_JP",
-        "jv" => "jv_ID", "Javanese" => "jv_ID",
-
-        "ka" => "ka_GE", "Georgian" => "ka_GE",
-        "kk" => "kk_KZ", "Kazakh" => "kk_KZ",
-        "kl" => "kl_GL", "Greenlandic" => "kl_GL",
-        "km" => "km_KH", "Khmer" => "km_KH",
-        "kn" => "kn_IN", "Kannada" => "kn_IN",
-        "ko" => "ko_KR", "Korean" => "ko_KR",
-        "ks" => "ks_IN", "Kashmiri" => "ks_IN",
-        "ku" => "ku_TR", "Kurdish" => "ku_TR",
-        "kw" => "kw_GB",
-        "ky" => "ky_KG", "Kirghiz" => "ky_KG",
-
-        "la" => "la_IT", "Latin" => "la_IT",
-        "lb" => "lb_LU",
-        "lg" => "lg_UG",
-        "li" => "li_NL",
-        "ln" => "ln_CD",
-        "lo" => "lo_LA", "Lao" => "lo_LA",
-        "lt" => "lt_LT", "Lithuanian" => "lt_LT",
-        "lv" => "lv_LV", "Latvian" => "lv_LV",
-
-        "mg" => "mg_MG", "Malagasy" => "mg_MG",
-        "mh" => "mh_MH",
-        "mi" => "mi_NZ",
-        "mk" => "mk_MK", "Macedonian" => "mk_MK",
-        "ml" => "ml_IN", "Malayalam" => "ml_IN",
-        "mn" => "mn_MN", "Mongolian" => "mn_MN",
-        "mo" => "mo_MD", "Moldavian" => "mo_MD",
-        "mr" => "mr_IN", "Marathi" => "mr_IN",
-        "ms" => "ms_MY", "Malay" => "ms_MY",
-        "mt" => "mt_MT", "Maltese" => "mt_MT",
-        "my" => "my_MM", "Burmese" => "my_MM",
-
-        "nb" => "nb_NO", "Norwegian" => "nb_NO",
-        "ne" => "ne_NP", "Nepali" => "ne_NP",
-        "nl" => "nl_NL", "Dutch" => "nl_NL",
-        "nn" => "nn_NO", "Nynorsk" => "nn_NO",
-        "nr" => "nr_ZA",
-        "ny" => "ny_MW", "Nyanja" => "ny_MW",
-        "oc" => "oc_FR",
-        "om" => "om_ET", "Oromo" => "om_ET",
-        "or" => "or_IN", "Oriya" => "or_IN",
-        "os" => "os_RU",
-
-        "pa" => "pa_IN", "Punjabi" => "pa_IN",
-        "pl" => "pl_PL", "Polish" => "pl_PL",
-        "ps" => "ps_AF", "Pashto" => "ps_AF",
-        "pt" => "pt_BR", "Portuguese" => "pt_BR",
-
-        "qu" => "qu_PE", "Quechua" => "qu_PE",
-
-        "rn" => "rn_BI", "Rundi" => "rn_BI",
-        "ro" => "ro_RO", "Romanian" => "ro_RO",
-        "ru" => "ru_RU", "Russian" => "ru_RU",
-        "rw" => "rw_RW", "Kinyarwanda" => "rw_RW",
-
-        "sa" => "sa_IN", "Sanskrit" => "sa_IN",
-        "sc" => "sc_IT",
-        "sd" => "sd_IN", "Sindhi" => "sd_IN",
-        "se" => "se_NO", "Sami" => "se_NO",
-        "si" => "si_LK", "Sinhalese" => "si_LK",
-        "sk" => "sk_SK", "Slovak" => "sk_SK",
-        "sl" => "sl_SI", "Slovenian" => "sl_SI",
-        "so" => "so_SO", "Somali" => "so_SO",
-        "sq" => "sq_AL", "Albanian" => "sq_AL",
-        "sr" => "sr_CS", "Serbian" => "sr_CS",
-        "ss" => "ss_ZA",
-        "st" => "st_ZA",
-        "su" => "su_ID", "Sundanese" => "su_ID",
-        "sv" => "sv_SE", "Swedish" => "sv_SE",
-        "sw" => "sw_TZ", "Swahili" => "sw_TZ",
-
-        "ta" => "ta_IN", "Tamil" => "ta_IN",
-        "te" => "te_IN", "Telugu" => "te_IN",
-        "tg" => "tg_TJ", "Tajiki" => "tg_TJ",
-        "th" => "th_TH", "Thai" => "th_TH",
-        "ti" => "ti_ER", "Tigrinya" => "ti_ER",
-        "tk" => "tk_TM", "Turkmen" => "tk_TM",
-        "tl" => "tl_PH", "Tagalog" => "tl_PH",
-        "tn" => "tn_ZA",
-        "to" => "to_TO", "Tongan" => "to_TO",
-        "tr" => "tr_TR", "Turkish" => "tr_TR",
-        "ts" => "ts_ZA",
-        "tt" => "tt_RU", "Tatar" => "tt_RU",
-
-        "ug" => "ug_CN", "Uighur" => "ug_CN",
-        "uk" => "uk_UA", "Ukrainian" => "uk_UA",
-        "ur" => "ur_PK", "Urdu" => "ur_PK",
-        "uz" => "uz_UZ", "Uzbek" => "uz_UZ",
-
-        "ve" => "ve_ZA",
-        "vi" => "vi_VN", "Vietnamese" => "vi_VN",
-
-        "wa" => "wa_BE",
-        "wo" => "wo_SN",
-
-        "xh" => "xh_ZA",
-
-        "yi" => "yi_US", "Yiddish" => "yi_US",
-        "yo" => "yo_NG",
-
-        "zh" => "zh_CN",
-        "zu" => "zu_ZA",
-
-
-
-        "agr" => "agr_PE",
-        "anp" => "anp_IN",
-        "ast" => "ast_ES",
-        "ayc" => "ayc_PE",
-
-        "bem" => "bem_ZM",
-        "ber" => "ber_DZ",
-        "bhb" => "bhb_IN",
-        "bho" => "bho_IN",
-        "brx" => "brx_IN",
-        "byn" => "byn_ER",
-
-        "chr" => "chr_US",
-        "crh" => "crh_UA",
-        "csb" => "csb_PL",
-
-        "doi" => "doi_IN",
-
-        "fil" => "fil_PH",
-        "fur" => "fur_IT",
-
-        "gez" => "gez_ER",
-        "grc" => "grc_GR",
-
-        "hak" => "hak_TW",
-        "hne" => "hne_IN",
-        "hsb" => "hsb_DE",
-        "hus" => "hus_MX",
-
-        "kab" => "kab_DZ",
-        "kok" => "kok_IN",
-
-        "lij" => "lij_IT",
-        "lzh" => "lzh_TW",
+    /// Deprecated or macro language subtags, mapped to their replacements.
+    pub static ref LANGUAGE_ALIASES: HashMap<&'static str, &'static str> = hashmap!{
+        "in" => "id",
+        "iw" => "he",
+        "ji" => "yi",
+        "jw" => "jv",
+        "mo" => "ro-MD",
+        "no" => "nb", // Sometimes the macro language code "no" is used instead of "nb".
+        "sh" => "sr-Latn",
+        "tl" => "fil",
+        "cmn" => "zh",
+    };
+
+    /// Deprecated region subtags, mapped to their replacements.
+    static ref REGION_ALIASES: HashMap<&'static str, &'static str> = hashmap!{
+        "BU" => "MM",
+        "CS" => "RS", // Apple uses the outdated "CS" country code instead of "RS".
+        "DD" => "DE",
+        "TP" => "TL",
+        "UK" => "GB",
+        "YU" => "RS",
+        "ZR" => "CD",
+    };
+
+    /// The likely script and region of a language.
+    pub static ref LIKELY_SUBTAGS: HashMap<&'static str, (&'static str, &'static str)> = hashmap!{
+        "aa" => ("Latn", "ET"), "af" => ("Latn", "ZA"), "ak" => ("Latn", "GH"), "am" => ("Ethi", "ET"),
+        "an" => ("Latn", "ES"), "ar" => ("Arab", "SA"), "as" => ("Beng", "IN"), "ay" => ("Latn", "PE"),
+        "az" => ("Latn", "AZ"),
+
+        "be" => ("Cyrl", "BY"), "bg" => ("Cyrl", "BG"), "bi" => ("Latn", "TV"), "bm" => ("Latn", "ML"),
+        "bn" => ("Beng", "BD"), "bo" => ("Tibt", "CN"), "br" => ("Latn", "FR"), "bs" => ("Latn", "BA"),
+
+        "ca" => ("Latn", "ES"), "ce" => ("Cyrl", "RU"), "cs" => ("Latn", "CZ"), "cv" => ("Cyrl", "RU"),
+        "cy" => ("Latn", "GB"),
+
+        "da" => ("Latn", "DK"), "de" => ("Latn", "DE"), "dv" => ("Thaa", "MV"), "dz" => ("Tibt", "BT"),
+
+        "el" => ("Grek", "GR"), "en" => ("Latn", "US"), "eo" => ("Latn", "001"), "es" => ("Latn", "ES"),
+        "et" => ("Latn", "EE"), "eu" => ("Latn", "ES"),
+
+        "fa" => ("Arab", "IR"), "ff" => ("Latn", "SN"), "fi" => ("Latn", "FI"), "fo" => ("Latn", "FO"),
+        "fr" => ("Latn", "FR"), "fy" => ("Latn", "DE"),
+
+        "ga" => ("Latn", "IE"), "gd" => ("Latn", "GB"), "gl" => ("Latn", "ES"), "gn" => ("Latn", "PY"),
+        "gu" => ("Gujr", "IN"), "gv" => ("Latn", "GB"),
+
+        "ha" => ("Latn", "NG"), "he" => ("Hebr", "IL"), "hi" => ("Deva", "IN"), "hr" => ("Latn", "HR"),
+        "ht" => ("Latn", "HT"), "hu" => ("Latn", "HU"), "hy" => ("Armn", "AM"),
+
+        "ia" => ("Latn", "FR"), "id" => ("Latn", "ID"), "ig" => ("Latn", "NG"), "ik" => ("Latn", "CA"),
+        "is" => ("Latn", "IS"), "it" => ("Latn", "IT"), "iu" => ("Cans", "CA"),
+
+        "ja" => ("Jpan", "JP"), "jv" => ("Latn", "ID"),
+
+        "ka" => ("Geor", "GE"), "kk" => ("Cyrl", "KZ"), "kl" => ("Latn", "GL"), "km" => ("Khmr", "KH"),
+        "kn" => ("Knda", "IN"), "ko" => ("Kore", "KR"), "ks" => ("Arab", "IN"), "ku" => ("Latn", "TR"),
+        "kw" => ("Latn", "GB"), "ky" => ("Cyrl", "KG"),
+
+        "la" => ("Latn", "IT"), "lb" => ("Latn", "LU"), "lg" => ("Latn", "UG"), "li" => ("Latn", "NL"),
+        "ln" => ("Latn", "CD"), "lo" => ("Laoo", "LA"), "lt" => ("Latn", "LT"), "lv" => ("Latn", "LV"),
+
+        "mg" => ("Latn", "MG"), "mh" => ("Latn", "MH"), "mi" => ("Latn", "NZ"), "mk" => ("Cyrl", "MK"),
+        "ml" => ("Mlym", "IN"), "mn" => ("Cyrl", "MN"), "mr" => ("Deva", "IN"), "ms" => ("Latn", "MY"),
+        "mt" => ("Latn", "MT"), "my" => ("Mymr", "MM"),
+
+        "nb" => ("Latn", "NO"), "ne" => ("Deva", "NP"), "nl" => ("Latn", "NL"), "nn" => ("Latn", "NO"),
+        "nr" => ("Latn", "ZA"), "ny" => ("Latn", "MW"),
+
+        "oc" => ("Latn", "FR"), "om" => ("Latn", "ET"), "or" => ("Orya", "IN"), "os" => ("Cyrl", "RU"),
+
+        "pa" => ("Guru", "IN"), "pl" => ("Latn", "PL"), "ps" => ("Arab", "AF"), "pt" => ("Latn", "BR"),
+
+        "qu" => ("Latn", "PE"),
+
+        "rn" => ("Latn", "BI"), "ro" => ("Latn", "RO"), "ru" => ("Cyrl", "RU"), "rw" => ("Latn", "RW"),
+
+        "sa" => ("Deva", "IN"), "sc" => ("Latn", "IT"), "sd" => ("Arab", "IN"), "se" => ("Latn", "NO"),
+        "si" => ("Sinh", "LK"), "sk" => ("Latn", "SK"), "sl" => ("Latn", "SI"), "so" => ("Latn", "SO"),
+        "sq" => ("Latn", "AL"), "sr" => ("Cyrl", "RS"), "ss" => ("Latn", "ZA"), "st" => ("Latn", "ZA"),
+        "su" => ("Latn", "ID"), "sv" => ("Latn", "SE"), "sw" => ("Latn", "TZ"),
+
+        "ta" => ("Taml", "IN"), "te" => ("Telu", "IN"), "tg" => ("Cyrl", "TJ"), "th" => ("Thai", "TH"),
+        "ti" => ("Ethi", "ER"), "tk" => ("Latn", "TM"), "tn" => ("Latn", "ZA"), "to" => ("Latn", "TO"),
+        "tr" => ("Latn", "TR"), "ts" => ("Latn", "ZA"), "tt" => ("Cyrl", "RU"),
+
+        "ug" => ("Arab", "CN"), "uk" => ("Cyrl", "UA"), "ur" => ("Arab", "PK"), "uz" => ("Latn", "UZ"),
 
-        "mag" => "mag_IN",
-        "mai" => "mai_IN",
-        "mhr" => "mhr_RU",
-        "miq" => "miq_NI",
-        "myv" => "myv_RU",
+        "ve" => ("Latn", "ZA"), "vi" => ("Latn", "VN"),
 
-        "nah" => "nah_MX",
-        "nan" => "nan_TW",
-        "nds" => "nds_DE",
-        "nhn" => "nhn_MX",
-        "niu" => "niu_NU",
-        "nso" => "nso_ZA",
+        "wa" => ("Latn", "BE"), "wo" => ("Latn", "SN"),
 
-        "pap" => "pap_AW",
+        "xh" => ("Latn", "ZA"),
 
-        "quy" => "quy_PE",
-        "quz" => "quz_PE",
+        "yi" => ("Hebr", "US"), "yo" => ("Latn", "NG"),
 
-        "raj" => "raj_IN",
+        "zh" => ("Hans", "CN"), "zu" => ("Latn", "ZA"),
 
-        "sat" => "sat_IN",
-        "sgs" => "sgs_LT",
-        "shs" => "shs_CA",
-        "sid" => "sid_ET",
-        "son" => "son_ML",
-        "szl" => "szl_PL",
+        "agr" => ("Latn", "PE"), "anp" => ("Deva", "IN"), "ast" => ("Latn", "ES"), "ayc" => ("Latn", "PE"),
 
-        "tcy" => "tcy_IN",
-        "the" => "the_NP",
-        "tig" => "tig_ER",
+        "bem" => ("Latn", "ZM"), "ber" => ("Latn", "DZ"), "bhb" => ("Deva", "IN"), "bho" => ("Deva", "IN"),
+        "brx" => ("Deva", "IN"), "byn" => ("Ethi", "ER"),
 
-        "unm" => "unm_US",
+        "chr" => ("Cher", "US"), "crh" => ("Latn", "UA"), "csb" => ("Latn", "PL"),
 
-        "wae" => "wae_CH",
-        "wal" => "wal_ET",
+        "doi" => ("Deva", "IN"),
 
-        "yue" => "yue_HK",
+        "fil" => ("Latn", "PH"), "fur" => ("Latn", "IT"),
 
+        "gez" => ("Ethi", "ER"), "grc" => ("Grek", "GR"),
 
+        "hak" => ("Hant", "TW"), "hne" => ("Deva", "IN"), "hsb" => ("Latn", "DE"), "hus" => ("Latn", "MX"),
 
-        "zh-Hant" => "zh_TW",
-        "zh-Hans" => "zh_CN",
+        "kab" => ("Latn", "DZ"), "kok" => ("Deva", "IN"),
 
-        "cmn" => "zh_CN",
-        "cmn_TW" => "zh_TW",
-        "cmn_CN" => "zh_CN",
+        "lij" => ("Latn", "IT"), "lzh" => ("Hant", "TW"),
 
-        "no" => "nb_NO",    // Sometimes the misspelled language code "no" is used instead of "nb".
-        "sr_RS" => "sr_CS", // Apple uses the outdated "CS" country code instead of "RS".
+        "mag" => ("Deva", "IN"), "mai" => ("Deva", "IN"), "mhr" => ("Cyrl", "RU"), "miq" => ("Latn", "NI"),
+        "myv" => ("Cyrl", "RU"),
+
+        "nah" => ("Latn", "MX"), "nan" => ("Hant", "TW"), "nds" => ("Latn", "DE"), "nhn" => ("Latn", "MX"),
+        "niu" => ("Latn", "NU"), "nso" => ("Latn", "ZA"),
+
+        "pap" => ("Latn", "AW"),
+
+        "quy" => ("Latn", "PE"), "quz" => ("Latn", "PE"),
+
+        "raj" => ("Deva", "IN"),
+
+        "sat" => ("Olck", "IN"), "sgs" => ("Latn", "LT"), "shs" => ("Latn", "CA"), "sid" => ("Latn", "ET"),
+        "son" => ("Latn", "ML"), "szl" => ("Latn", "PL"),
+
+        "tcy" => ("Knda", "IN"), "the" => ("Deva", "NP"), "tig" => ("Ethi", "ER"),
+
+        "unm" => ("Latn", "US"),
+
+        "wae" => ("Latn", "CH"), "wal" => ("Ethi", "ET"),
+
+        "yue" => ("Hant", "HK"),
+    };
+
+    /// The likely script of a language in a region, if different from the one in `LIKELY_SUBTAGS`.
+    pub static ref LIKELY_SCRIPTS: HashMap<&'static str, &'static str> = hashmap!{
+        "az_IR" => "Arab",
+        "mn_CN" => "Mong",
+        "pa_PK" => "Arab",
+        "sr_ME" => "Latn",
+        "uz_AF" => "Arab",
+        "yue_CN" => "Hans",
+        "zh_HK" => "Hant",
+        "zh_MO" => "Hant",
+        "zh_TW" => "Hant",
+    };
+
+    /// The likely region of a language in a script, if different from the one in `LIKELY_SUBTAGS`.
+    pub static ref LIKELY_REGIONS: HashMap<&'static str, &'static str> = hashmap!{
+        "az_Arab" => "IR",
+        "mn_Mong" => "CN",
+        "pa_Arab" => "PK",
+        "uz_Arab" => "AF",
+        "yue_Hans" => "CN",
+        "zh_Hant" => "TW",
     };
 }
 
-/// Gets the locale ID of an `*.lproj` folder. The locale ID is always of the form like `"en_US"`.
-pub fn locale_id(lproj_name: &str) -> &str {
-    debug_assert!(lproj_name.ends_with(".lproj"));
-    let name = &lproj_name[.. lproj_name.len()-6];
-    LOCALE_MAP.get(name).unwrap_or(&name)
+/// A locale parsed from a BCP 47 language tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Locale {
+    /// The language subtag in lowercase, e.g. `zh`.
+    pub language: String,
+    /// The script subtag in title case, e.g. `Hant`.
+    pub script: Option<String>,
+    /// The region subtag in uppercase, e.g. `TW` or `419`.
+    pub region: Option<String>,
+    /// The variant subtags in lowercase, e.g. `valencia`.
+    pub variants: Vec<String>,
+}
+
+fn is_alphabetic(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+fn is_alphanumeric(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn title_case(s: &str) -> String {
+    let mut result = s.to_ascii_lowercase();
+    result[.. 1].make_ascii_uppercase();
+    result
+}
+
+impl Locale {
+    /// Parses a language tag like `zh-Hant-HK` or `zh_Hant_HK`. Extensions and private-use subtags are ignored.
+    ///
+    /// Returns `None` if the tag is not well-formed.
+    pub fn parse(tag: &str) -> Option<Locale> {
+        let mut subtags = tag.split(|c| c == '-' || c == '_').peekable();
+
+        let language = subtags.next().unwrap();
+        let is_language_length = match language.len() { 2 ..= 3 | 5 ..= 8 => true, _ => false };
+        if !is_language_length || !is_alphabetic(language) {
+            return None;
+        }
+        let mut locale = Locale { language: language.to_ascii_lowercase(), ..Locale::default() };
+
+        if let Some(&script) = subtags.peek() {
+            if script.len() == 4 && is_alphabetic(script) {
+                locale.script = Some(title_case(script));
+                subtags.next();
+            }
+        }
+
+        if let Some(&region) = subtags.peek() {
+            let is_numeric = region.bytes().all(|b| b.is_ascii_digit());
+            if region.len() == 2 && is_alphabetic(region) || region.len() == 3 && is_numeric {
+                locale.region = Some(region.to_ascii_uppercase());
+                subtags.next();
+            }
+        }
+
+        for variant in subtags {
+            let starts_with_digit = variant.bytes().next().map_or(false, |b| b.is_ascii_digit());
+            if 5 <= variant.len() && variant.len() <= 8 || variant.len() == 4 && starts_with_digit {
+                if !is_alphanumeric(variant) {
+                    return None;
+                }
+                locale.variants.push(variant.to_ascii_lowercase());
+            } else if variant.len() == 1 {
+                break; // extensions and private-use subtags.
+            } else {
+                return None;
+            }
+        }
+
+        Some(locale)
+    }
+
+    /// Canonicalizes the locale, replacing deprecated subtags and filling in the likely region. The script is removed
+    /// if it is the likely one of the language in the region.
+    pub fn canonicalize(mut self) -> Locale {
+        if let Some(alias) = LANGUAGE_ALIASES.get(&*self.language).and_then(|a| Locale::parse(a)) {
+            self.language = alias.language;
+            self.script = self.script.or(alias.script);
+            self.region = self.region.or(alias.region);
+        }
+        if let Some(region) = self.region.as_ref().and_then(|r| REGION_ALIASES.get(&**r)) {
+            self.region = Some(region.to_string());
+        }
+
+        let (default_script, default_region) = match LIKELY_SUBTAGS.get(&*self.language) {
+            Some(&subtags) => subtags,
+            None => return self,
+        };
+
+        if self.region.is_none() {
+            let language_script = self.script.as_ref().map(|s| format!("{}_{}", self.language, s));
+            let likely_region = language_script.and_then(|ls| LIKELY_REGIONS.get(&*ls).cloned());
+            self.region = Some(likely_region.unwrap_or(default_region).to_owned());
+        }
+
+        let likely_script = {
+            let language_region = format!("{}_{}", self.language, self.region.as_ref().unwrap());
+            *LIKELY_SCRIPTS.get(&*language_region).unwrap_or(&default_script)
+        };
+        if self.script.as_ref().map(|s| &**s) == Some(likely_script) {
+            self.script = None;
+        }
+
+        self
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.language)?;
+        for subtag in self.script.iter().chain(&self.region).chain(&self.variants) {
+            write!(f, "_{}", subtag)?;
+        }
+        Ok(())
+    }
 }
 
 #[test]
-fn test_locale_id() {
-    assert_eq!(locale_id("en.lproj"), "en_US");
-    assert_eq!(locale_id("French.lproj"), "fr_FR");
-    assert_eq!(locale_id("es_419.lproj"), "es_419");
-    assert_eq!(locale_id("unknown.lproj"), "unknown");
+fn test_canonicalize() {
+    let canonical_id = |tag: &str| Locale::parse(tag).map(|locale| locale.canonicalize().to_string());
+    assert_eq!(canonical_id("zh-Hant-HK"), Some("zh_HK".to_owned()));
+    assert_eq!(canonical_id("sr_Latn"), Some("sr_Latn_RS".to_owned()));
+    assert_eq!(canonical_id("iw"), Some("he_IL".to_owned()));
+    assert_eq!(canonical_id("ca-ES-valencia"), Some("ca_ES_valencia".to_owned()));
+    assert_eq!(canonical_id("en-US-x-private"), Some("en_US".to_owned()));
+    assert_eq!(canonical_id("Base"), None);
+}
+
+/// Checks whether a document field name is a locale ID like `en_US` or `eo_001`, as opposed to metadata fields like
+/// `UI_ELEMENT` which are always uppercase.
+pub fn is_locale_field(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_lowercase())
 }
 
 /*
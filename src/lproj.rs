@@ -18,13 +18,18 @@
 
 use std::path::{Path, PathBuf};
 use std::ffi::OsStr;
-use std::collections::{BTreeMap, HashMap, hash_map};
+use std::collections::{BTreeMap, HashMap, HashSet, hash_map};
 use std::fs::{File, read_dir};
 use walkdir::{WalkDir, WalkDirIterator};
-use serde_json::{Value, to_string_pretty};
+use serde_json::{Value, Map, to_string_pretty, from_str};
 use plist::Plist;
+use unicode_segmentation::UnicodeSegmentation;
 
 use error::{Result, ErrorKind};
+use options::Platform;
+use winres::{ResourceFile, read_resource_file};
+use ib::{read_interface_file, INTERFACE_EXTENSIONS};
+use locales::is_locale_field;
 
 /// Finds all localized bundles inside the given directory.
 pub fn scan_localized_bundles<P: AsRef<Path>>(root: P) -> BTreeMap<PathBuf, Vec<String>> {
@@ -60,6 +65,47 @@ fn test_scan_localized_bundles() {
 }
 
 
+/// Information about a bundle from its `Info.plist`.
+#[derive(Debug, Default)]
+pub struct BundleInfo {
+    /// The `CFBundleIdentifier`, e.g. `com.apple.springboard`.
+    pub identifier: Option<String>,
+    /// The `CFBundleDevelopmentRegion`, e.g. `en`.
+    pub development_region: Option<String>,
+}
+
+/// Reads the `Info.plist` of the bundle containing the `*.lproj` folders.
+///
+/// The `Info.plist` is looked up in the bundle itself (iOS), in its parent when the bundle is a `Resources` folder
+/// (macOS apps), and in its `Contents` folder.
+pub fn read_bundle_info(bundle_path: &Path) -> BundleInfo {
+    let mut candidates = vec![bundle_path.join("Info.plist"), bundle_path.join("Contents/Info.plist")];
+    if bundle_path.file_name() == Some(OsStr::new("Resources")) {
+        candidates.push(bundle_path.with_file_name("Info.plist"));
+    }
+
+    let dict = candidates.into_iter().filter_map(|path| {
+        let file = File::open(path).ok()?;
+        match Plist::read(file) {
+            Ok(Plist::Dictionary(dict)) => Some(dict),
+            _ => None,
+        }
+    }).next();
+
+    let mut dict = match dict {
+        Some(dict) => dict,
+        None => return BundleInfo::default(),
+    };
+    let mut take_string = |key: &str| match dict.remove(key) {
+        Some(Plist::String(s)) => Some(s),
+        _ => None,
+    };
+    BundleInfo {
+        identifier: take_string("CFBundleIdentifier"),
+        development_region: take_string("CFBundleDevelopmentRegion"),
+    }
+}
+
 /// Converts a plist into a JSON value.
 ///
 /// Panics if the input cannot be converted to JSON (but it should not happen with strings/stringsdict files).
@@ -85,8 +131,40 @@ fn into_es_string(value: Value) -> String {
     }
 }
 
+/// The key of the format string of a `*.stringsdict` entry.
+pub const FORMAT_KEY: &str = "NSStringLocalizedFormatKey";
+
+/// Parses a `*.stringsdict` entry, which is stored as a JSON object in the document. Returns `None` for any other
+/// translation, including plain texts starting with `{` such as `{0} items`.
+pub fn parse_strings_dict(text: &str) -> Option<BTreeMap<String, Value>> {
+    if !text.starts_with('{') {
+        return None;
+    }
+    from_str::<BTreeMap<String, Value>>(text).ok().and_then(|dict| {
+        if dict.contains_key(FORMAT_KEY) { Some(dict) } else { None }
+    })
+}
+
+/// Whether the translation is a `*.stringsdict` entry.
+pub fn is_strings_dict(text: &str) -> bool {
+    parse_strings_dict(text).is_some()
+}
+
+#[test]
+fn test_parse_strings_dict() {
+    let entry = r#"{"NSStringLocalizedFormatKey": "%#@items@", "items": {"one": "an item", "other": "%d items"}}"#;
+    assert_eq!(parse_strings_dict(entry), Some(btreemap! {
+        "NSStringLocalizedFormatKey".to_owned() => json!("%#@items@"),
+        "items".to_owned() => json!({"one": "an item", "other": "%d items"}),
+    }));
+    assert!(is_strings_dict(entry));
+    assert!(!is_strings_dict("{0} items"));
+    assert!(!is_strings_dict("{\"name\": \"JSON in a translation\"}"));
+    assert!(!is_strings_dict("Delete"));
+}
+
 /// Tuple of the file name of the `*.strings` file and the localization key.
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 struct Key {
     file: String,
     key: String,
@@ -94,13 +172,16 @@ struct Key {
 
 /// Stores all localized strings in a bundle.
 #[derive(Default)]
-pub struct LocalizedBundle<'a>(HashMap<Key, HashMap<&'a str, String>>);
-// {key => {locale => value}}
+pub struct LocalizedBundle<'a> {
+    entries: HashMap<Key, HashMap<&'a str, String>>, // {key => {locale => value}}
+    comments: HashMap<Key, String>,
+    ui_elements: HashMap<Key, String>,
+}
 
 impl<'a> LocalizedBundle<'a> {
     /// Adds a key-value pair in the given locale.
     fn add_entry(&mut self, locale_id: &'a str, key: Key, value: Value) {
-        let values = self.0.entry(key).or_insert_with(HashMap::new);
+        let values = self.entries.entry(key).or_insert_with(HashMap::new);
         values.insert(locale_id, into_es_string(value));
     }
 
@@ -149,34 +230,177 @@ impl<'a> LocalizedBundle<'a> {
         Ok(())
     }
 
+    /// Resolves the Interface Builder object IDs used as keys of `*.strings` files, by reading the `*.storyboard`,
+    /// `*.xib`, `*.storyboardc` or `*.nib` file of the same name in `Base.lproj`, the bundle itself, or any other
+    /// `*.lproj` folder.
+    ///
+    /// Every resolved key gets a `UI_ELEMENT` like `UIButton normalTitle`, and the text in the interface file is used
+    /// as the `Base` translation if it is missing.
+    pub fn read_interface_files(&mut self, bundle_path: &Path, localizations: &[String]) {
+        let strings_files = self.entries.keys()
+            .filter_map(|k| if k.file.ends_with(".strings") { Some(k.file.clone()) } else { None })
+            .collect::<HashSet<_>>();
+
+        let mut dirs = vec![bundle_path.join("Base.lproj"), bundle_path.to_owned()];
+        dirs.extend(localizations.iter().map(|l| bundle_path.join(l)));
+
+        for strings_file in strings_files {
+            let stem = &strings_file[.. strings_file.len()-8];
+            let mut candidates = dirs.iter().flat_map(|dir| {
+                INTERFACE_EXTENSIONS.iter().map(move |ext| dir.join(format!("{}.{}", stem, ext)))
+            });
+            let objects = match candidates.find(|p| p.exists()).map(|p| read_interface_file(&p)) {
+                Some(Ok(objects)) => objects,
+                _ => continue, // not an interface file, or it cannot be read; just leave the keys unresolved.
+            };
+
+            for (key, values) in self.entries.iter_mut().filter(|&(k, _)| k.file == strings_file) {
+                let (id, property) = match key.key.find('.') {
+                    Some(i) => (&key.key[.. i], &key.key[i+1 ..]),
+                    None => continue,
+                };
+                if let Some(object) = objects.get(id) {
+                    self.ui_elements.insert(key.clone(), format!("{} {}", object.class, property));
+                    if let Some(text) = object.texts.get(property) {
+                        values.entry("Base").or_insert_with(|| text.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads all strings of a Windows resource file (`*.resx`, `*.resw` or `*.mui`) of a given locale.
+    pub fn read_resource_file(&mut self, locale_id: &'a str, resource_file: &ResourceFile) -> Result<()> {
+        for rs in read_resource_file(&resource_file.path)? {
+            let key = Key { file: resource_file.file.clone(), key: rs.name };
+            if let Some(comment) = rs.comment {
+                self.comments.entry(key.clone()).or_insert(comment);
+            }
+            self.add_entry(locale_id, key, Value::String(rs.value));
+        }
+        Ok(())
+    }
+
     /// After all `*.lproj`s are read, converts this instance into an iterator to read the entries.
-    pub fn into_iter(self, filename: &Path) -> LocalizedBundleIntoIter<'a> {
+    ///
+    /// The `Base` translations are merged into the development locale where it is missing, and are also kept in the
+    /// `BASE` field.
+    pub fn into_iter(self, fields: BundleFields<'a>) -> LocalizedBundleIntoIter<'a> {
         LocalizedBundleIntoIter {
-            filename: filename.to_string_lossy().into_owned(),
-            it: self.0.into_iter(),
+            filename: fields.path.to_string_lossy().into_owned(),
+            bundle_id: fields.bundle_id,
+            platform: fields.platform,
+            development_locale: fields.development_locale,
+            build: fields.build,
+            it: self.entries.into_iter(),
+            comments: self.comments,
+            ui_elements: self.ui_elements,
         }
     }
 }
 
 
+/// Fields shared by all documents of a bundle.
+pub struct BundleFields<'a> {
+    /// Path of the bundle, stored in the `BUNDLE` field.
+    pub path: &'a Path,
+    /// Identifier of the bundle which stays the same across builds, stored in the `BUNDLE_ID` field.
+    pub bundle_id: String,
+    /// Stored in the `PLATFORM` field.
+    pub platform: Platform,
+    /// The locale `Base` translations are merged into, stored in the `SOURCE_LOCALE` field.
+    pub development_locale: &'a str,
+    /// Build identifier, stored in the `BUILD` field if present.
+    pub build: Option<&'a str>,
+}
+
 pub struct LocalizedBundleIntoIter<'a> {
     filename: String,
+    bundle_id: String,
+    platform: Platform,
+    development_locale: &'a str,
+    build: Option<&'a str>,
     it: hash_map::IntoIter<Key, HashMap<&'a str, String>>,
+    comments: HashMap<Key, String>,
+    ui_elements: HashMap<Key, String>,
 }
 
 impl<'a> Iterator for LocalizedBundleIntoIter<'a> {
     type Item = Value;
 
     fn next(&mut self) -> Option<Value> {
+        let development_locale = self.development_locale;
         self.it.next().map(|(k, mut v)| {
+            if let Some(base) = v.remove("Base") {
+                v.entry(development_locale).or_insert_with(|| base.clone());
+                v.insert("BASE", base);
+            }
+            let length = length_metrics(&v, development_locale);
+            v.insert("SOURCE_LOCALE", development_locale.to_owned());
+            if let Some(comment) = self.comments.remove(&k) {
+                v.insert("COMMENT", comment);
+            }
+            if let Some(ui_element) = self.ui_elements.remove(&k) {
+                v.insert("UI_ELEMENT", ui_element);
+            }
             v.insert("BUNDLE", self.filename.clone());
+            v.insert("BUNDLE_ID", self.bundle_id.clone());
+            v.insert("PLATFORM", self.platform.name().to_owned());
+            if let Some(build) = self.build {
+                v.insert("BUILD", build.to_owned());
+            }
             v.insert("FILE", k.file);
             v.insert("KEY", k.key);
-            json!(v)
+            let mut document = json!(v);
+            if !length.is_empty() {
+                document["LENGTH"] = Value::Object(length);
+            }
+            document
         })
     }
 }
 
+/// Computes the `LENGTH` field of a document, e.g. `{"de_DE": {"chars": 9, "graphemes": 9, "ratio": 1.5}}`.
+///
+/// Every translation gets its number of characters and grapheme clusters. Translations other than the development
+/// locale also get the expansion ratio, the number of grapheme clusters relative to the source text. `*.stringsdict`
+/// entries are skipped.
+fn length_metrics(translations: &HashMap<&str, String>, development_locale: &str) -> Map<String, Value> {
+    let source_graphemes = match translations.get(development_locale) {
+        Some(source) if !is_strings_dict(source) => source.graphemes(true).count(),
+        _ => 0,
+    };
+    let texts = translations.iter().filter(|&(locale, text)| is_locale_field(locale) && !is_strings_dict(text));
+    texts.map(|(locale, text)| {
+        let graphemes = text.graphemes(true).count();
+        let mut metrics = json!({"chars": text.chars().count(), "graphemes": graphemes});
+        if source_graphemes > 0 && *locale != development_locale {
+            metrics["ratio"] = json!(graphemes as f64 / source_graphemes as f64);
+        }
+        (locale.to_string(), metrics)
+    }).collect()
+}
+
+#[test]
+fn test_length_metrics() {
+    let translations = hashmap! {
+        "en" => "Delete".to_owned(),
+        "de_DE" => "Löschen".to_owned(),
+        "hi_IN" => "हटाएं".to_owned(),
+        "fr_FR" => "Supprimer définitivement".to_owned(),
+        "ja_JP" => "{\"NSStringLocalizedFormatKey\": \"%#@items@\"}".to_owned(),
+        "zh_CN" => "{0}个项目".to_owned(),
+        "BASE" => "Delete".to_owned(),
+    };
+    assert_eq!(Value::Object(length_metrics(&translations, "en")), json!({
+        "en": {"chars": 6, "graphemes": 6},
+        "de_DE": {"chars": 7, "graphemes": 7, "ratio": 7.0 / 6.0},
+        "hi_IN": {"chars": 5, "graphemes": 3, "ratio": 0.5},
+        "fr_FR": {"chars": 24, "graphemes": 24, "ratio": 4.0},
+        "zh_CN": {"chars": 6, "graphemes": 6, "ratio": 1.0},
+    }));
+}
+
 /*
 
 Copyright 2017 kennytm
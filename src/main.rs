@@ -11,6 +11,9 @@ extern crate plist;
 extern crate clap;
 extern crate rayon;
 extern crate pbr;
+extern crate xml;
+extern crate toml;
+extern crate unicode_segmentation;
 
 macro_rules! eprintln {
     ($($e:expr),*) => {
@@ -23,55 +26,706 @@ mod es;
 mod error;
 mod options;
 mod locales;
+mod aliases;
+mod winres;
+mod ib;
+mod diff;
+mod check;
+mod coverage;
+mod consistency;
+mod lint;
+mod suggest;
+mod catalog;
+mod manifest;
+mod scroll;
 
-use std::io::Write;
+use std::io::{BufReader, Read, Write, stdout};
 use std::default::Default;
 use std::time::{Instant, Duration};
 use std::fmt::{self, Display, Formatter};
 use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::fs::File;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use pbr::ProgressBar;
 
-use options::Options;
-use lproj::{scan_localized_bundles, LocalizedBundle};
-use locales::locale_id;
+use options::{Options, Platform, Command, DiffOptions, CheckOptions, CoverageOptions, ConsistencyOptions};
+use options::{LintOptions, SuggestOptions, PruneOptions, PruneTarget, ReportFormat};
+use lproj::{scan_localized_bundles, read_bundle_info, LocalizedBundle, LocalizedBundleIntoIter, BundleFields};
+use lproj::is_strings_dict;
+use winres::{scan_resource_sets, ResourceFile};
+use diff::{diff, Snapshot};
+use check::{check_document, source_text};
+use coverage::{Coverage, BundleReport};
+use consistency::Terminology;
+use lint::{Lookup, MatchKind, Finding, lookup_query};
+use suggest::{Suggestion, suggestion_query, best_suggestion};
+use catalog::{CatalogFormat, fill_strings, fill_xcstrings, fill_xliff};
+use manifest::{Manifest, fingerprint_files, same_contents, apple_bundle_files};
+use locales::is_locale_field;
+use aliases::{locale_id, canonical_locale_id, add_aliases, effective_aliases};
 use es::Es;
 use error::{ErrorKind, ResultExt, Result};
 
 quick_main!(run);
 
+/// The development region assumed when a bundle does not specify one.
+const DEFAULT_DEVELOPMENT_REGION: &str = "en";
+
 fn run() -> Result<()> {
-    let opt = Options::parse();
+    let (opt, command) = Options::parse();
+
+    if let Some(ref path) = opt.locale_map {
+        load_locale_map(path).chain_err(|| ErrorKind::ReadLocaleMap(path.clone()))?;
+    }
+
+    match command {
+        Command::Import => import(opt),
+        Command::Locales => {
+            print_locales();
+            Ok(())
+        },
+        Command::Diff(ref diff_opt) => diff_builds(&opt, diff_opt),
+        Command::Check(ref check_opt) => check_formats(&opt, check_opt),
+        Command::Coverage(ref coverage_opt) => coverage_report(&opt, coverage_opt),
+        Command::Consistency(ref consistency_opt) => consistency_report(&opt, consistency_opt),
+        Command::Lint(ref lint_opt) => lint_translations(&opt, lint_opt),
+        Command::Suggest(ref suggest_opt) => suggest_translations(&opt, suggest_opt),
+        Command::Prune(ref prune_opt) => prune(&opt, prune_opt),
+    }
+}
 
+/// Loads the user-defined locale aliases from a JSON or TOML file.
+fn load_locale_map(path: &str) -> Result<()> {
+    let mut content = String::new();
+    File::open(path)?.read_to_string(&mut content)?;
+    let aliases: HashMap<String, String> = if path.ends_with(".toml") {
+        toml::from_str(&content)?
+    } else {
+        serde_json::from_str(&content)?
+    };
+    add_aliases(aliases);
+    Ok(())
+}
+
+/// Prints the locale ID of every known locale name.
+fn print_locales() {
+    for (name, id) in effective_aliases() {
+        println!("{:<16} {}", name, id);
+    }
+}
+
+/// Scans the root directory and imports all translations into Elasticsearch.
+///
+/// With `--manifest`, only the bundles which changed since the import recorded in the manifest are imported, and the
+/// manifest is updated afterwards.
+fn import(opt: Options) -> Result<()> {
+    let root = opt.root.expect("checked in Options::parse");
+    let root_path = Path::new(&root);
+    let build = opt.build.as_ref().map(|b| &**b);
     let start_time = Instant::now();
 
     eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
     let es = Es::new(opt.base, &opt.index, &opt.type_);
     es.create_index(opt.shards, opt.replicas)?;
 
-    eprintln!("Scanning for localized bundles from `{}`...", opt.root);
-    let localized_bundles = scan_localized_bundles(opt.root);
+    let manifest_path = opt.manifest.as_ref().map(Path::new);
+    let settings = json!({
+        "root": root,
+        "platform": opt.platform.name(),
+        "build": build,
+        "index": opt.index,
+        "type": opt.type_,
+    });
+    let previous = match manifest_path {
+        Some(path) => match Manifest::load(path)? {
+            Some(ref manifest) if manifest.settings != settings => {
+                eprintln!("warning: the manifest `{}` was created with different options, importing everything",
+                          path.display());
+                None
+            },
+            previous => previous,
+        },
+        None => None,
+    };
+    let mut manifest = manifest_path.map(|_| Manifest::new(settings));
+
+    eprintln!("Scanning for localized bundles from `{}`...", root);
+    match opt.platform {
+        Platform::Apple => {
+            let bundles = scan_localized_bundles(&root);
+            let bundles = select_changed_bundles(&es, opt.platform, build, previous.as_ref(), manifest.as_mut(),
+                                                 bundles, apple_bundle_files)?;
+            import_bundles(start_time, bundles, |bundle_path, localizations| {
+                read_apple_bundle(root_path, &bundle_path, &localizations, build, |translations| {
+                    es.add_translations(translations).chain_err(|| ErrorKind::IndexTranslations(bundle_path.clone()))
+                })
+            })
+        },
+        Platform::Windows => {
+            let bundles = scan_resource_sets(&root);
+            let files_of = |_: &Path, resource_files: &[ResourceFile]| -> Vec<PathBuf> {
+                resource_files.iter().map(|rf| rf.path.clone()).collect()
+            };
+            let bundles = select_changed_bundles(&es, opt.platform, build, previous.as_ref(), manifest.as_mut(),
+                                                 bundles, files_of)?;
+            import_bundles(start_time, bundles, |bundle_path, resource_files| {
+                read_windows_bundle(root_path, &bundle_path, &resource_files, build, |translations| {
+                    es.add_translations(translations).chain_err(|| ErrorKind::IndexTranslations(bundle_path.clone()))
+                })
+            })
+        },
+    }?;
+
+    if let (Some(path), Some(manifest)) = (manifest_path, manifest) {
+        manifest.save(path)?;
+        eprintln!("Saved the manifest to `{}`.", path.display());
+    }
+    Ok(())
+}
+
+/// Records the files of every bundle into the `manifest`, and keeps only the bundles which changed since the
+/// `previous` manifest. The documents of the changed bundles and of the bundles which disappeared are deleted first.
+/// Without a manifest, all bundles are kept.
+///
+/// Without a previous manifest, every bundle is imported and nothing is deleted, just like an import without
+/// `--manifest`. Otherwise even the new bundles are deleted first, in case the previous import was interrupted.
+fn select_changed_bundles<T, F>(es: &Es, platform: Platform, build: Option<&str>, previous: Option<&Manifest>,
+                                manifest: Option<&mut Manifest>, bundles: BTreeMap<PathBuf, Vec<T>>, files_of: F)
+    -> Result<BTreeMap<PathBuf, Vec<T>>>
+    where F: Fn(&Path, &[T]) -> Vec<PathBuf>
+{
+    let manifest = match manifest {
+        Some(manifest) => manifest,
+        None => return Ok(bundles),
+    };
+
+    let total_count = bundles.len();
+    let mut deleted_count = 0;
+    let mut changed = BTreeMap::new();
+    for (bundle_path, items) in bundles {
+        let bundle = bundle_path.to_string_lossy().into_owned();
+        let previous_files = previous.and_then(|p| p.bundles.get(&bundle));
+        let files = fingerprint_files(&bundle_path, &files_of(&bundle_path, &items), previous_files)?;
+        if !previous_files.map_or(false, |p| same_contents(p, &files)) {
+            if previous.is_some() {
+                deleted_count += es.delete_by_query(&bundle_query(&bundle, platform, build))?;
+            }
+            changed.insert(bundle_path, items);
+        }
+        manifest.bundles.insert(bundle, files);
+    }
+
+    let mut removed_count = 0;
+    for bundle in previous.into_iter().flat_map(|p| p.bundles.keys()) {
+        if !manifest.bundles.contains_key(bundle) {
+            removed_count += 1;
+            deleted_count += es.delete_by_query(&bundle_query(bundle, platform, build))?;
+        }
+    }
+
+    eprintln!("{} of {} bundles changed and {} removed since the last import, deleted {} outdated translations.",
+              changed.len(), total_count, removed_count, deleted_count);
+    Ok(changed)
+}
+
+/// Matches the documents of a bundle imported from the platform, in the given build or without any build.
+fn bundle_query(bundle: &str, platform: Platform, build: Option<&str>) -> serde_json::Value {
+    let mut query = json!({
+        "bool": {
+            "filter": [
+                {"term": {"BUNDLE.keyword": bundle}},
+                {"term": {"PLATFORM.keyword": platform.name()}},
+            ],
+        },
+    });
+    match build {
+        Some(build) => query["bool"]["filter"].as_array_mut().unwrap().push(json!({"term": {"BUILD.keyword": build}})),
+        None => query["bool"]["must_not"] = json!({"exists": {"field": "BUILD"}}),
+    }
+    query
+}
+
+/// Identifies a bundle without `CFBundleIdentifier` by its path relative to the root directory.
+fn relative_bundle_id(root: &Path, bundle_path: &Path) -> String {
+    bundle_path.strip_prefix(root).unwrap_or(bundle_path).to_string_lossy().into_owned()
+}
+
+/// Reads the translations of an Apple bundle from its `*.lproj` folders, and passes them to `consume`.
+fn read_apple_bundle<R, F>(root: &Path, bundle_path: &Path, localizations: &[String], build: Option<&str>, consume: F)
+    -> Result<R>
+    where F: FnOnce(LocalizedBundleIntoIter) -> Result<R>
+{
+    let info = read_bundle_info(bundle_path);
+    let development_region = info.development_region.unwrap_or_else(|| DEFAULT_DEVELOPMENT_REGION.to_owned());
+    let development_locale = canonical_locale_id(&development_region);
+
+    let loc_ids = localizations.iter().map(|l| locale_id(l)).collect::<Vec<_>>();
+
+    let mut bundle = LocalizedBundle::default();
+    for (localization, loc_id) in localizations.iter().zip(&loc_ids) {
+        let lproj_path = bundle_path.join(localization);
+        bundle.read_lproj(loc_id, &lproj_path).chain_err(|| ErrorKind::ReadLproj(lproj_path))?;
+    }
+    bundle.read_interface_files(bundle_path, localizations);
+
+    consume(bundle.into_iter(BundleFields {
+        path: bundle_path,
+        bundle_id: info.identifier.unwrap_or_else(|| relative_bundle_id(root, bundle_path)),
+        platform: Platform::Apple,
+        development_locale: &development_locale,
+        build: build,
+    }))
+}
+
+/// Reads the translations of a Windows resource set, and passes them to `consume`.
+fn read_windows_bundle<R, F>(root: &Path, bundle_path: &Path, resource_files: &[ResourceFile], build: Option<&str>,
+                             consume: F) -> Result<R>
+    where F: FnOnce(LocalizedBundleIntoIter) -> Result<R>
+{
+    let loc_ids = resource_files.iter().map(|rf| match rf.culture {
+        Some(ref culture) => canonical_locale_id(culture),
+        None => "Base".to_owned(),
+    }).collect::<Vec<_>>();
+
+    let mut bundle = LocalizedBundle::default();
+    for (resource_file, loc_id) in resource_files.iter().zip(&loc_ids) {
+        bundle.read_resource_file(loc_id, resource_file)
+            .chain_err(|| ErrorKind::ReadResourceFile(resource_file.path.clone()))?;
+    }
+
+    let development_locale = canonical_locale_id(DEFAULT_DEVELOPMENT_REGION);
+    consume(bundle.into_iter(BundleFields {
+        path: bundle_path,
+        bundle_id: relative_bundle_id(root, bundle_path),
+        platform: Platform::Windows,
+        development_locale: &development_locale,
+        build: build,
+    }))
+}
+
+/// Compares the translations of two builds, and prints the report to stdout.
+fn diff_builds(opt: &Options, diff_opt: &DiffOptions) -> Result<()> {
+    let (old, new) = if diff_opt.roots {
+        (scan_snapshot(opt.platform, &diff_opt.old)?, scan_snapshot(opt.platform, &diff_opt.new)?)
+    } else {
+        eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
+        let es = Es::new(opt.base.clone(), &opt.index, &opt.type_);
+        let old = read_snapshot(&es, &diff_opt.old).chain_err(|| ErrorKind::ReadBuild(diff_opt.old.clone()))?;
+        let new = read_snapshot(&es, &diff_opt.new).chain_err(|| ErrorKind::ReadBuild(diff_opt.new.clone()))?;
+        (old, new)
+    };
+
+    let changes = diff(&old, &new);
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    match diff_opt.format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &diff::to_json(&changes))?;
+            writeln!(out)?;
+        },
+        ReportFormat::Csv => out.write_all(diff::to_csv(&changes).as_bytes())?,
+        _ => unreachable!("rejected by DiffOptions::subcommand"),
+    }
+
+    eprintln!("Found {} changed translations.", changes.len());
+    Ok(())
+}
+
+/// Reads all translations of a build from the index.
+fn read_snapshot(es: &Es, build: &str) -> Result<Snapshot> {
+    eprintln!("Reading translations of build `{}`...", build);
+    let mut snapshot = Snapshot::default();
+    es.scan(json!({"term": {"BUILD.keyword": build}}), |document| snapshot.add_document(&document))?;
+    Ok(snapshot)
+}
+
+/// Scans a root directory, and passes the translations of every bundle to `f` one by one.
+fn scan_root<F>(platform: Platform, root: &str, mut f: F) -> Result<()>
+    where F: FnMut(LocalizedBundleIntoIter)
+{
+    eprintln!("Scanning for localized bundles from `{}`...", root);
+    let root_path = Path::new(root);
+    match platform {
+        Platform::Apple => for (bundle_path, localizations) in scan_localized_bundles(root) {
+            read_apple_bundle(root_path, &bundle_path, &localizations, None, |translations| {
+                f(translations);
+                Ok(())
+            })?;
+        },
+        Platform::Windows => for (bundle_path, resource_files) in scan_resource_sets(root) {
+            read_windows_bundle(root_path, &bundle_path, &resource_files, None, |translations| {
+                f(translations);
+                Ok(())
+            })?;
+        },
+    }
+    Ok(())
+}
+
+/// Reads all translations by scanning a root directory.
+fn scan_snapshot(platform: Platform, root: &str) -> Result<Snapshot> {
+    let mut snapshot = Snapshot::default();
+    scan_root(platform, root, |translations| translations.for_each(|t| snapshot.add_document(&t)))?;
+    Ok(snapshot)
+}
+
+/// Checks the format specifiers of all translations in a root directory, and prints the mismatches to stdout.
+///
+/// Fails if any mismatch is found, so the check can be used in continuous integration.
+fn check_formats(opt: &Options, check_opt: &CheckOptions) -> Result<()> {
+    let mut problems = Vec::new();
+    scan_root(opt.platform, &check_opt.root, |translations| check_translations(translations, &mut problems))?;
+
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    match check_opt.format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &problems)?;
+            writeln!(out)?;
+        },
+        ReportFormat::Text => for problem in &problems {
+            let field = |name: &str| problem[name].as_str().unwrap_or("");
+            let file = Path::new(field("bundle")).join(field("file"));
+            writeln!(out, "{}: {} ({}): {}", file.display(), field("key"), field("locale"), field("message"))?;
+        },
+        _ => unreachable!("rejected by CheckOptions::subcommand"),
+    }
+
+    if !problems.is_empty() {
+        bail!(ErrorKind::FormatMismatches(problems.len()));
+    }
+    eprintln!("No format specifier mismatches found.");
+    Ok(())
+}
+
+/// Checks the format specifiers of every translation in a bundle, adding the mismatches to `problems`.
+fn check_translations(translations: LocalizedBundleIntoIter, problems: &mut Vec<serde_json::Value>) {
+    for document in translations {
+        problems.extend(check_document(&document).into_iter().map(|(locale, issue)| json!({
+            "bundle": document["BUNDLE"],
+            "file": document["FILE"],
+            "key": document["KEY"],
+            "locale": locale,
+            "kind": issue.kind.name(),
+            "message": issue.message,
+            "source": source_text(&document),
+            "translation": document[&locale],
+        })));
+    }
+}
+
+/// Reports the coverage of every locale of the bundles in a root directory to stdout.
+fn coverage_report(opt: &Options, coverage_opt: &CoverageOptions) -> Result<()> {
+    let mut coverage = Coverage::default();
+    scan_root(opt.platform, &coverage_opt.root, |translations| {
+        translations.for_each(|t| coverage.add_document(&t))
+    })?;
+
+    let reports = coverage.reports();
+    let with_keys = !coverage_opt.summary;
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    match coverage_opt.format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &coverage::to_json(&reports, with_keys))?;
+            writeln!(out)?;
+        },
+        ReportFormat::Text => out.write_all(coverage_to_text(&reports, with_keys).as_bytes())?,
+        _ => unreachable!("rejected by CoverageOptions::subcommand"),
+    }
+    Ok(())
+}
+
+/// Converts the coverage reports into plain text. The missing and identical keys are listed if `with_keys` is true.
+fn coverage_to_text(reports: &[BundleReport], with_keys: bool) -> String {
+    let mut text = String::new();
+    for bundle in reports {
+        text.push_str(&format!("{} ({} keys)\n", bundle.bundle, bundle.total));
+        for report in &bundle.locales {
+            text.push_str(&format!(
+                "    {:<16} {:>6.1}%  {} missing, {} identical\n",
+                report.locale, report.percentage(), report.missing.len(), report.identical.len(),
+            ));
+            if with_keys {
+                for key in &report.missing {
+                    text.push_str(&format!("        missing    {} {}\n", key.file, key.key));
+                }
+                for key in &report.identical {
+                    text.push_str(&format!("        identical  {} {}\n", key.file, key.key));
+                }
+            }
+        }
+    }
+    text
+}
+
+#[test]
+fn test_coverage_to_text() {
+    let key = coverage::FileKey { file: "General.strings".to_owned(), key: "ABOUT".to_owned() };
+    let reports = [BundleReport {
+        bundle: "com.apple.Preferences",
+        total: 4,
+        locales: vec![coverage::LocaleReport { locale: "de_DE", total: 4, missing: vec![&key], identical: vec![] }],
+    }];
+    assert_eq!(coverage_to_text(&reports, true), "com.apple.Preferences (4 keys)\n\
+                                                  \x20   de_DE              75.0%  1 missing, 0 identical\n\
+                                                  \x20       missing    General.strings ABOUT\n");
+}
+
+/// Reports the source texts translated differently across bundles to stdout, reading either the index or a root
+/// directory.
+fn consistency_report(opt: &Options, consistency_opt: &ConsistencyOptions) -> Result<()> {
+    let mut terminology = Terminology::default();
+    if let Some(ref root) = consistency_opt.scan {
+        scan_root(opt.platform, root, |translations| translations.for_each(|t| terminology.add_document(&t)))?;
+    } else {
+        eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
+        let es = Es::new(opt.base.clone(), &opt.index, &opt.type_);
+        let query = match opt.build {
+            Some(ref build) => json!({"term": {"BUILD.keyword": build}}),
+            None => json!({"match_all": {}}),
+        };
+        eprintln!("Reading translations...");
+        es.scan(query, |document| terminology.add_document(&document))?;
+    }
+
+    let inconsistencies = terminology.inconsistencies();
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    match consistency_opt.format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &consistency::to_json(&inconsistencies))?;
+            writeln!(out)?;
+        },
+        ReportFormat::Csv => out.write_all(consistency::to_csv(&inconsistencies).as_bytes())?,
+        ReportFormat::Text => out.write_all(consistency::to_text(&inconsistencies).as_bytes())?,
+        ReportFormat::Sarif => unreachable!("rejected by ConsistencyOptions::subcommand"),
+    }
+    eprintln!("Found {} inconsistently translated texts.", inconsistencies.len());
+    Ok(())
+}
+
+/// Compares the translations of the bundles in a root directory with the index, and prints the translations which
+/// differ from the dominant ones to stdout.
+fn lint_translations(opt: &Options, lint_opt: &LintOptions) -> Result<()> {
+    eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
+    let es = Es::new(opt.base.clone(), &opt.index, &opt.type_);
+    let build = opt.build.as_ref().map(|b| &**b);
+    let root_path = Path::new(&lint_opt.root);
+    let mut lookups = HashMap::new();
+    let mut findings = Vec::new();
+
+    eprintln!("Scanning for localized bundles from `{}`...", lint_opt.root);
+    for (bundle_path, localizations) in scan_localized_bundles(&lint_opt.root) {
+        let folders = localizations.iter().map(|l| (locale_id(l), &**l)).collect::<HashMap<_, _>>();
+        read_apple_bundle(root_path, &bundle_path, &localizations, None, |translations| {
+            for document in translations {
+                let source_locale = document["SOURCE_LOCALE"].as_str().unwrap_or("");
+                let source = match source_text(&document) {
+                    Some(s) if !s.trim().is_empty() && !is_strings_dict(s) => s,
+                    _ => continue,
+                };
+                let fields = document.as_object().into_iter().flat_map(|d| d.iter());
+                let texts = fields
+                    .filter(|&(field, _)| is_locale_field(field) && field != source_locale)
+                    .filter_map(|(locale, t)| t.as_str().filter(|t| !is_strings_dict(t)).map(|t| (&**locale, t)))
+                    .collect::<Vec<_>>();
+                if texts.is_empty() {
+                    continue;
+                }
+
+                let locales = texts.iter().map(|&(locale, _)| locale).collect::<Vec<_>>();
+                let cache_key = (source_locale.to_owned(), source.to_owned(), locales.join(","));
+                if !lookups.contains_key(&cache_key) {
+                    let lookup = lookup_translations(&es, build, source_locale, source, &locales)?;
+                    lookups.insert(cache_key.clone(), lookup);
+                }
+                let lookup = match lookups[&cache_key] {
+                    Some(ref lookup) => lookup,
+                    None => continue,
+                };
+
+                let file = document["FILE"].as_str().unwrap_or("");
+                for (locale, translation) in texts {
+                    let (suggestion, support, total, confidence) = match lookup.suggest(locale, translation) {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    if confidence < lint_opt.min_confidence {
+                        continue;
+                    }
+                    let folder = folders.get(locale).cloned().unwrap_or(locale);
+                    findings.push(Finding {
+                        path: bundle_path.join(folder).join(file).to_string_lossy().into_owned(),
+                        key: document["KEY"].as_str().unwrap_or("").to_owned(),
+                        locale: locale.to_owned(),
+                        source: source.to_owned(),
+                        translation: translation.to_owned(),
+                        suggestion: suggestion.to_owned(),
+                        confidence: confidence,
+                        support: support,
+                        total: total,
+                        kind: lookup.kind,
+                        matched_source: lookup.matched_source.clone(),
+                    });
+                }
+            }
+            Ok(())
+        })?;
+    }
 
-    let mut progress_bar = ProgressBar::new(localized_bundles.len() as u64);
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    let report = match lint_opt.format {
+        ReportFormat::Sarif => lint::to_sarif(&findings),
+        ReportFormat::Json => lint::to_json(&findings),
+        _ => unreachable!("rejected by LintOptions::subcommand"),
+    };
+    serde_json::to_writer_pretty(&mut out, &report)?;
+    writeln!(out)?;
+    eprintln!("Found {} translations differing from the index.", findings.len());
+    Ok(())
+}
+
+/// Looks up the translations of a source text in the index, trying an exact match before a fuzzy one.
+fn lookup_translations(es: &Es, build: Option<&str>, source_locale: &str, source: &str, locales: &[&str])
+    -> Result<Option<Lookup>>
+{
+    for &kind in &[MatchKind::Exact, MatchKind::Fuzzy] {
+        let reply = es.search(&lookup_query(source_locale, source, locales, build, kind))?;
+        if let Some(lookup) = Lookup::from_reply(kind, source, reply) {
+            return Ok(Some(lookup));
+        }
+    }
+    Ok(None)
+}
+
+/// Fills in a translation file with the best translations found in the index, and writes it to the output file or
+/// stdout.
+fn suggest_translations(opt: &Options, suggest_opt: &SuggestOptions) -> Result<()> {
+    let input_path = Path::new(&suggest_opt.input);
+    let format = match CatalogFormat::from_path(input_path) {
+        Some(format) => format,
+        None => bail!(ErrorKind::UnsupportedCatalog(suggest_opt.input.clone())),
+    };
+
+    eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
+    let es = Es::new(opt.base.clone(), &opt.index, &opt.type_);
+    let build = opt.build.as_ref().map(|b| &**b);
+    let mut suggestions = HashMap::new();
+    let mut suggested_count = 0;
+
+    let output = {
+        let mut suggest = |source_language: &str, source: &str, target_language: &str| -> Result<Option<Suggestion>> {
+            if source.trim().is_empty() {
+                return Ok(None);
+            }
+            let source_locale = canonical_locale_id(source_language);
+            let target_locale = canonical_locale_id(target_language);
+            let cache_key = (source_locale, source.to_owned(), target_locale);
+            if !suggestions.contains_key(&cache_key) {
+                let (ref source_locale, _, ref target_locale) = cache_key;
+                let reply = es.search(&suggestion_query(source_locale, source, target_locale, build))?;
+                suggestions.insert(cache_key.clone(), best_suggestion(source_locale, source, &reply));
+            }
+            let suggestion = suggestions[&cache_key].clone();
+            if suggestion.is_some() {
+                suggested_count += 1;
+            }
+            Ok(suggestion)
+        };
+
+        eprintln!("Suggesting translations for `{}`...", suggest_opt.input);
+        match format {
+            CatalogFormat::Strings => {
+                if suggest_opt.locales.len() != 1 {
+                    bail!(ErrorKind::InvalidLocaleCount);
+                }
+                let mut content = String::new();
+                File::open(input_path)?.read_to_string(&mut content)?;
+                fill_strings(&content, &suggest_opt.locales[0], |source, locale| {
+                    suggest(&suggest_opt.source_locale, source, locale)
+                })?
+            },
+            CatalogFormat::XcStrings => {
+                if suggest_opt.locales.is_empty() {
+                    bail!(ErrorKind::InvalidLocaleCount);
+                }
+                let mut content = String::new();
+                File::open(input_path)?.read_to_string(&mut content)?;
+                fill_xcstrings(&content, &suggest_opt.locales, &mut suggest)?
+            },
+            CatalogFormat::Xliff => {
+                let input = BufReader::new(File::open(input_path)?);
+                let default_locale = suggest_opt.locales.first().map(|l| &**l);
+                fill_xliff(input, default_locale, |source_language, target_language, source| {
+                    suggest(source_language, source, target_language)
+                })?
+            },
+        }
+    };
+
+    match suggest_opt.output {
+        Some(ref path) => File::create(path)?.write_all(output.as_bytes())?,
+        None => stdout().write_all(output.as_bytes())?,
+    }
+    eprintln!("Suggested {} translations.", suggested_count);
+    Ok(())
+}
+
+/// Deletes the documents of a build, bundle, path or platform from the index, after counting them.
+fn prune(opt: &Options, prune_opt: &PruneOptions) -> Result<()> {
+    let (description, target) = match prune_opt.target {
+        PruneTarget::Build(ref build) => (format!("build `{}`", build), json!({"term": {"BUILD.keyword": build}})),
+        PruneTarget::Bundle(ref bundle_id) => {
+            (format!("bundle `{}`", bundle_id), json!({"term": {"BUNDLE_ID.keyword": bundle_id}}))
+        },
+        PruneTarget::Path(ref prefix) => {
+            (format!("bundles under `{}`", prefix), json!({"prefix": {"BUNDLE.keyword": prefix}}))
+        },
+        PruneTarget::Source(platform) => {
+            (format!("platform `{}`", platform.name()), json!({"term": {"PLATFORM.keyword": platform.name()}}))
+        },
+    };
+    let mut filters = vec![target];
+    match (&prune_opt.target, &opt.build) {
+        (&PruneTarget::Build(_), _) | (_, &None) => {},
+        (_, &Some(ref build)) => filters.push(json!({"term": {"BUILD.keyword": build}})),
+    }
+    let query = json!({"bool": {"filter": filters}});
+
+    eprintln!("Connecting to Elasticsearch cluster at `{}`.", opt.base);
+    let es = Es::new(opt.base.clone(), &opt.index, &opt.type_);
+    let count = es.count(&query)?;
+    eprintln!("Found {} documents of the {}.", count, description);
+    if prune_opt.dry_run || count == 0 {
+        return Ok(());
+    }
+
+    let deleted_count = es.delete_by_query(&query)?;
+    eprintln!("Deleted {} documents.", deleted_count);
+    Ok(())
+}
+
+/// Imports every bundle in parallel using `import_bundle`, while showing a progress bar.
+///
+/// Returns the total number of translations imported.
+fn import_bundles<T, F>(start_time: Instant, bundles: BTreeMap<PathBuf, Vec<T>>, import_bundle: F) -> Result<usize>
+    where T: Send, F: Fn(PathBuf, Vec<T>) -> Result<usize> + Sync
+{
+    let mut progress_bar = ProgressBar::new(bundles.len() as u64);
     progress_bar.set_width(Some(100));
     progress_bar.set_max_refresh_rate(Some(Duration::from_millis(500)));
     let mut progress_bar = Mutex::new(progress_bar);
 
-    let total_count: Result<usize> = localized_bundles.into_par_iter().map(|(bundle_path, localizations)| -> Result<usize> {
-        let mut bundle = LocalizedBundle::default();
-        for localization in &localizations {
-            let lproj_path = bundle_path.join(localization);
-            let loc_id = locale_id(localization);
-            bundle.read_lproj(loc_id, &lproj_path).chain_err(|| ErrorKind::ReadLproj(lproj_path))?;
-        }
-
-        let translations = bundle.into_iter(&bundle_path);
-        let count = es.add_translations(translations).chain_err(|| ErrorKind::IndexTranslations(bundle_path))?;
+    let total_count: Result<usize> = bundles.into_par_iter().map(|(bundle_path, items)| -> Result<usize> {
+        let count = import_bundle(bundle_path, items)?;
         {
             let mut lock = progress_bar.lock().unwrap();
             lock.inc();
         }
-
         Ok(count)
     }).sum();
     let total_count = total_count?;
@@ -80,7 +734,7 @@ fn run() -> Result<()> {
     let finish_msg = format!("Finished, imported {} translations in {}", total_count, PrettyDuration(duration));
     progress_bar.get_mut().unwrap().finish_println(&finish_msg);
 
-    Ok(())
+    Ok(total_count)
 }
 
 /// Wrapper to print a duration in the format `59s` or `2m30s`.
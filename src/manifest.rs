@@ -0,0 +1,274 @@
+//! Manifest of the imported files, for incremental re-scans.
+//!
+//! After an import with `--manifest`, the size, modification time and content hash of every file read from each
+//! bundle are saved. The next import with the same manifest only re-reads the bundles whose files changed. Files with
+//! the same size and modification time as before are assumed to be unchanged and are not hashed again, while files
+//! which were merely touched are hashed but do not cause the bundle to be re-read.
+
+use std::collections::BTreeMap;
+use std::fs::{File, read_dir, rename};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde_json::{self, Value};
+
+use error::Result;
+use ib::INTERFACE_EXTENSIONS;
+
+/// Version of the manifest format. Manifests of other versions are ignored.
+const VERSION: u64 = 1;
+
+/// The size, modification time and content hash of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub mtime: u64,
+    /// 64-bit FNV-1a hash of the content, in hexadecimal.
+    pub hash: String,
+}
+
+/// The fingerprints of the files of a bundle, keyed by their paths relative to the bundle.
+pub type BundleFiles = BTreeMap<String, Fingerprint>;
+
+/// The files of every imported bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// The options of the import, e.g. the root directory and the build. A manifest is only reused by an import with
+    /// the same settings.
+    pub settings: Value,
+    /// The files of every bundle, keyed by the bundle path (the `BUNDLE` field).
+    pub bundles: BTreeMap<String, BundleFiles>,
+}
+
+impl Manifest {
+    pub fn new(settings: Value) -> Manifest {
+        Manifest {
+            settings: settings,
+            bundles: BTreeMap::new(),
+        }
+    }
+
+    /// Reads a manifest. Returns `None` if the file does not exist or is of another version.
+    pub fn load(path: &Path) -> Result<Option<Manifest>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let content: Value = serde_json::from_reader(BufReader::new(file))?;
+        Ok(Manifest::from_json(&content))
+    }
+
+    /// Writes the manifest. The file is replaced atomically, so an interrupted import keeps the previous manifest.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        {
+            let mut file = File::create(&temp_path)?;
+            serde_json::to_writer(&mut file, &self.to_json())?;
+            file.write_all(b"\n")?;
+        }
+        rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    fn from_json(content: &Value) -> Option<Manifest> {
+        if content["version"].as_u64() != Some(VERSION) {
+            return None;
+        }
+        let bundles = content["bundles"].as_object()?.iter().map(|(bundle, files)| {
+            let files = files.as_object().into_iter().flat_map(|f| f.iter()).filter_map(|(file, fingerprint)| {
+                let fingerprint = Fingerprint {
+                    size: fingerprint["size"].as_u64()?,
+                    mtime: fingerprint["mtime"].as_u64()?,
+                    hash: fingerprint["hash"].as_str()?.to_owned(),
+                };
+                Some((file.clone(), fingerprint))
+            }).collect();
+            (bundle.clone(), files)
+        }).collect();
+        Some(Manifest {
+            settings: content["settings"].clone(),
+            bundles: bundles,
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let bundles = self.bundles.iter().map(|(bundle, files)| {
+            let files = files.iter().map(|(file, fingerprint)| {
+                let fingerprint = json!({
+                    "size": fingerprint.size,
+                    "mtime": fingerprint.mtime,
+                    "hash": fingerprint.hash,
+                });
+                (file.clone(), fingerprint)
+            }).collect::<serde_json::Map<_, _>>();
+            (bundle.clone(), Value::Object(files))
+        }).collect::<serde_json::Map<_, _>>();
+        json!({
+            "version": VERSION,
+            "settings": self.settings,
+            "bundles": bundles,
+        })
+    }
+}
+
+#[test]
+fn test_manifest_json() {
+    let mut manifest = Manifest::new(json!({"root": "/Volumes/Erie14E304.D11D111OS", "build": "14E304"}));
+    manifest.bundles.insert("/System/Library/CoreServices/SpringBoard.app".to_owned(), btreemap! {
+        "en.lproj/SpringBoard.strings".to_owned() => Fingerprint {
+            size: 1234,
+            mtime: 1_490_000_000_123_456_789,
+            hash: "af63dc4c8601ec8c".to_owned(),
+        },
+    });
+    let content = manifest.to_json();
+    assert_eq!(content["bundles"]["/System/Library/CoreServices/SpringBoard.app"]["en.lproj/SpringBoard.strings"],
+        json!({"size": 1234, "mtime": 1_490_000_000_123_456_789u64, "hash": "af63dc4c8601ec8c"}));
+    assert_eq!(Manifest::from_json(&content), Some(manifest));
+    assert_eq!(Manifest::from_json(&json!({"version": 0, "bundles": {}})), None);
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x100_0000_01b3;
+
+/// Continues a 64-bit FNV-1a hash with more bytes.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(FNV_OFFSET_BASIS, b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a(fnv1a(FNV_OFFSET_BASIS, b"foo"), b"bar"), fnv1a(FNV_OFFSET_BASIS, b"foobar"));
+}
+
+/// Hashes the content of a file.
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0; 65536];
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            return Ok(format!("{:016x}", hash));
+        }
+        hash = fnv1a(hash, &buffer[..len]);
+    }
+}
+
+/// Computes the fingerprints of the files of a bundle. The hash is reused from the `previous` fingerprints of the
+/// bundle when the size and modification time of a file are unchanged.
+pub fn fingerprint_files(bundle_path: &Path, files: &[PathBuf], previous: Option<&BundleFiles>)
+    -> io::Result<BundleFiles>
+{
+    let mut fingerprints = BundleFiles::new();
+    for path in files {
+        let metadata = path.metadata()?;
+        let mtime = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let size = metadata.len();
+        let mtime = mtime.as_secs() * 1_000_000_000 + u64::from(mtime.subsec_nanos());
+
+        let name = path.strip_prefix(bundle_path).unwrap_or(path).to_string_lossy().into_owned();
+        let hash = match previous.and_then(|p| p.get(&name)) {
+            Some(fp) if fp.size == size && fp.mtime == mtime => fp.hash.clone(),
+            _ => hash_file(path)?,
+        };
+        fingerprints.insert(name, Fingerprint { size: size, mtime: mtime, hash: hash });
+    }
+    Ok(fingerprints)
+}
+
+/// Checks whether two sets of fingerprints have the same files with the same content, ignoring modification times.
+pub fn same_contents(a: &BundleFiles, b: &BundleFiles) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|((name_a, fp_a), (name_b, fp_b))| {
+        name_a == name_b && fp_a.hash == fp_b.hash
+    })
+}
+
+#[test]
+fn test_same_contents() {
+    let fingerprint = |mtime: u64, hash: &str| Fingerprint { size: 3, mtime: mtime, hash: hash.to_owned() };
+    let old = btreemap! { "a".to_owned() => fingerprint(1, "0001"), "b".to_owned() => fingerprint(1, "0002") };
+    let touched = btreemap! { "a".to_owned() => fingerprint(2, "0001"), "b".to_owned() => fingerprint(1, "0002") };
+    let modified = btreemap! { "a".to_owned() => fingerprint(2, "0003"), "b".to_owned() => fingerprint(1, "0002") };
+    let renamed = btreemap! { "a".to_owned() => fingerprint(1, "0001"), "c".to_owned() => fingerprint(1, "0002") };
+    assert!(same_contents(&old, &touched));
+    assert!(!same_contents(&old, &modified));
+    assert!(!same_contents(&old, &renamed));
+    assert!(!same_contents(&old, &BundleFiles::new()));
+}
+
+/// Lists the files read from an Apple bundle: everything inside the `*.lproj` folders, the `Info.plist`, and the
+/// interface files (`*.storyboard`, `*.xib`, `*.storyboardc` and `*.nib`) next to the `*.lproj` folders.
+pub fn apple_bundle_files(bundle_path: &Path, localizations: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for localization in localizations {
+        list_files_recursively(&bundle_path.join(localization), &mut files);
+    }
+
+    let mut candidates = vec![bundle_path.join("Info.plist"), bundle_path.join("Contents/Info.plist")];
+    if bundle_path.file_name().map_or(false, |name| name == "Resources") {
+        candidates.push(bundle_path.with_file_name("Info.plist"));
+    }
+    if let Ok(entries) = read_dir(bundle_path) {
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let is_interface_file = path.extension().and_then(|ext| ext.to_str())
+                .map_or(false, |ext| INTERFACE_EXTENSIONS.contains(&ext));
+            if !is_interface_file {
+                continue;
+            }
+            if path.is_dir() {
+                list_files_recursively(&path, &mut files); // compiled `*.storyboardc` or `*.nib` folders.
+            } else {
+                candidates.push(path);
+            }
+        }
+    }
+    files.extend(candidates.into_iter().filter(|path| path.is_file()));
+
+    files.sort();
+    files
+}
+
+/// Appends all files inside a folder to `files`. Unreadable folders are skipped, like in the scan itself.
+fn list_files_recursively(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(ref t) if t.is_dir() => list_files_recursively(&path, files),
+            Ok(ref t) if t.is_file() => files.push(path),
+            _ => {},
+        }
+    }
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
@@ -1,14 +1,70 @@
 //! Parse command line options.
 
-use clap::AppSettings;
+use std::str::FromStr;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use clap::{AppSettings, Arg, ArgMatches, SubCommand, Error, ErrorKind};
 use hyper::Url;
 use structopt::StructOpt;
 
+/// The platform the localized resources come from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Platform {
+    /// `*.lproj/*.strings` of macOS, iOS, etc.
+    Apple,
+    /// `*.resx`, `*.resw` and `*.mui` of Windows.
+    Windows,
+}
+
+impl Platform {
+    /// The name of the platform, stored in the `PLATFORM` field of every document.
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Apple => "apple",
+            Platform::Windows => "windows",
+        }
+    }
+}
+
+/// Error of parsing a platform name other than `apple` or `windows`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPlatform(pub String);
+
+impl Display for UnknownPlatform {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "unknown platform `{}`, expecting `apple` or `windows`", self.0)
+    }
+}
+
+impl StdError for UnknownPlatform {
+    fn description(&self) -> &str {
+        "unknown platform, expecting `apple` or `windows`"
+    }
+}
+
+impl FromStr for Platform {
+    type Err = UnknownPlatform;
+
+    fn from_str(s: &str) -> Result<Self, UnknownPlatform> {
+        match s {
+            "apple" => Ok(Platform::Apple),
+            "windows" => Ok(Platform::Windows),
+            _ => Err(UnknownPlatform(s.to_owned())),
+        }
+    }
+}
+
 /// Command line options.
 #[derive(StructOpt, Debug)]
 pub struct Options {
     #[structopt(help="Root directory to scan for localized bundles")]
-    pub root: String,
+    pub root: Option<String>,
+
+    #[structopt(short="-p", long="--platform", help="Kind of localized resources to scan for, `apple` or `windows`", default_value="apple")]
+    pub platform: Platform,
+
+    #[structopt(short="-b", long="--build", help="Build identifier stored with every translation, e.g. `14E304`")]
+    pub build: Option<String>,
 
     #[structopt(short="-u", long="--url", help="Acesss point of the Elasticsearch cluster", default_value="http://127.0.0.1:9200")]
     pub base: Url,
@@ -24,15 +80,333 @@ pub struct Options {
 
     #[structopt(long="--replicas", help="Number of replicas of the new index", default_value="1")]
     pub replicas: u32,
+
+    #[structopt(long="--locale-map", help="JSON or TOML file of additional locale aliases, e.g. `{\"en-IN\": \"en_IN\"}`")]
+    pub locale_map: Option<String>,
+
+    #[structopt(long="--manifest", help="File recording the imported files, to only re-import the bundles changed since the last import")]
+    pub manifest: Option<String>,
+}
+
+/// The action to perform.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Scans the root directory and imports the translations into Elasticsearch.
+    Import,
+    /// Prints the effective locale alias table.
+    Locales,
+    /// Compares the translations of two builds.
+    Diff(DiffOptions),
+    /// Checks the format specifiers of the translations in a root directory.
+    Check(CheckOptions),
+    /// Reports the missing and untranslated keys in a root directory.
+    Coverage(CoverageOptions),
+    /// Reports source texts which are translated inconsistently.
+    Consistency(ConsistencyOptions),
+    /// Compares the translations in a root directory with the index.
+    Lint(LintOptions),
+    /// Fills in a translation file with the translations found in the index.
+    Suggest(SuggestOptions),
+    /// Deletes documents from the index.
+    Prune(PruneOptions),
+}
+
+/// Output format of reports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Csv,
+    Sarif,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "sarif" => Ok(ReportFormat::Sarif),
+            _ => Err(format!("unknown format `{}`, expecting `text`, `json`, `csv` or `sarif`", s)),
+        }
+    }
+}
+
+/// Options of the `diff` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// The old build identifier, or root directory if `roots` is set.
+    pub old: String,
+    /// The new build identifier, or root directory if `roots` is set.
+    pub new: String,
+    /// Whether to scan two root directories instead of reading two builds from the index.
+    pub roots: bool,
+    pub format: ReportFormat,
+}
+
+impl DiffOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("diff")
+            .about("Compares the translations of two builds")
+            .arg(Arg::with_name("old").required(true).help("Old build identifier, or root directory with --roots"))
+            .arg(Arg::with_name("new").required(true).help("New build identifier, or root directory with --roots"))
+            .arg(Arg::with_name("roots").long("roots").help("Scans two root directories instead of reading the index"))
+            .arg(Arg::with_name("format").long("format").possible_values(&["json", "csv"]).default_value("json")
+                .help("Format of the report"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> DiffOptions {
+        DiffOptions {
+            old: matches.value_of("old").unwrap().to_owned(),
+            new: matches.value_of("new").unwrap().to_owned(),
+            roots: matches.is_present("roots"),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+        }
+    }
+}
+
+/// Options of the `check` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CheckOptions {
+    /// Root directory to scan for localized bundles.
+    pub root: String,
+    pub format: ReportFormat,
+}
+
+impl CheckOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("check")
+            .about("Checks that the format specifiers of every translation match the source")
+            .arg(Arg::with_name("root").required(true).help("Root directory to scan for localized bundles"))
+            .arg(Arg::with_name("format").long("format").possible_values(&["text", "json"]).default_value("text")
+                .help("Format of the report"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> CheckOptions {
+        CheckOptions {
+            root: matches.value_of("root").unwrap().to_owned(),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+        }
+    }
+}
+
+/// Options of the `coverage` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CoverageOptions {
+    /// Root directory to scan for localized bundles.
+    pub root: String,
+    /// Whether to omit the lists of missing and identical keys.
+    pub summary: bool,
+    pub format: ReportFormat,
+}
+
+impl CoverageOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("coverage")
+            .about("Reports the keys which are missing or untranslated in each locale")
+            .arg(Arg::with_name("root").required(true).help("Root directory to scan for localized bundles"))
+            .arg(Arg::with_name("summary").long("summary").help("Prints only the coverage without listing the keys"))
+            .arg(Arg::with_name("format").long("format").possible_values(&["text", "json"]).default_value("text")
+                .help("Format of the report"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> CoverageOptions {
+        CoverageOptions {
+            root: matches.value_of("root").unwrap().to_owned(),
+            summary: matches.is_present("summary"),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+        }
+    }
+}
+
+/// Options of the `consistency` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ConsistencyOptions {
+    /// Root directory to scan instead of reading the index.
+    pub scan: Option<String>,
+    pub format: ReportFormat,
+}
+
+impl ConsistencyOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("consistency")
+            .about("Reports source texts which are translated differently across bundles")
+            .arg(Arg::with_name("scan").long("scan").takes_value(true).value_name("root")
+                .help("Scans a root directory instead of reading the index (of the --build, if given)"))
+            .arg(Arg::with_name("format").long("format").possible_values(&["text", "json", "csv"]).default_value("text")
+                .help("Format of the report"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> ConsistencyOptions {
+        ConsistencyOptions {
+            scan: matches.value_of("scan").map(String::from),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+        }
+    }
+}
+
+/// Options of the `lint` subcommand.
+#[derive(Debug, PartialEq)]
+pub struct LintOptions {
+    /// Root directory to scan for localized bundles.
+    pub root: String,
+    /// Translations are only reported with at least this confidence.
+    pub min_confidence: f64,
+    pub format: ReportFormat,
+}
+
+impl LintOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("lint")
+            .about("Reports translations which differ from the dominant translation in the index")
+            .arg(Arg::with_name("root").required(true).help("Root directory to scan for localized bundles"))
+            .arg(Arg::with_name("min_confidence").long("min-confidence").takes_value(true).default_value("0.5")
+                .validator(|v| v.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Minimum confidence from 0 to 1 of the reported translations"))
+            .arg(Arg::with_name("format").long("format").possible_values(&["sarif", "json"]).default_value("sarif")
+                .help("Format of the report"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> LintOptions {
+        LintOptions {
+            root: matches.value_of("root").unwrap().to_owned(),
+            min_confidence: matches.value_of("min_confidence").unwrap().parse().unwrap(),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+        }
+    }
+}
+
+/// Options of the `suggest` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SuggestOptions {
+    /// The `*.strings`, `*.xcstrings` or XLIFF file to translate.
+    pub input: String,
+    /// Target locales, as language tags like `de` or `zh-Hans`.
+    pub locales: Vec<String>,
+    /// Language of the `*.strings` file.
+    pub source_locale: String,
+    /// Output file, or stdout if missing.
+    pub output: Option<String>,
+}
+
+impl SuggestOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        SubCommand::with_name("suggest")
+            .about("Fills in a translation file with the best translations found in the index")
+            .arg(Arg::with_name("input").required(true).help("The *.strings, *.xcstrings or *.xliff file to translate"))
+            .arg(Arg::with_name("locale").long("locale").takes_value(true).multiple(true).number_of_values(1)
+                .help("Target locale, required for *.strings (once) and *.xcstrings (one or more)"))
+            .arg(Arg::with_name("source_locale").long("source-locale").takes_value(true).default_value("en")
+                .help("Language of the *.strings file"))
+            .arg(Arg::with_name("output").short("o").long("output").takes_value(true)
+                .help("Output file, defaults to stdout"))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> SuggestOptions {
+        SuggestOptions {
+            input: matches.value_of("input").unwrap().to_owned(),
+            locales: matches.values_of("locale").map_or_else(Vec::new, |v| v.map(String::from).collect()),
+            source_locale: matches.value_of("source_locale").unwrap().to_owned(),
+            output: matches.value_of("output").map(String::from),
+        }
+    }
+}
+
+/// The documents deleted by the `prune` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PruneTarget {
+    /// All documents of a build, by the `BUILD` field.
+    Build(String),
+    /// All documents of a bundle, by the `BUNDLE_ID` field.
+    Bundle(String),
+    /// All documents of the bundles under a path, by the prefix of the `BUNDLE` field.
+    Path(String),
+    /// All documents imported from a platform, by the `PLATFORM` field.
+    Source(Platform),
+}
+
+/// Options of the `prune` subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PruneOptions {
+    pub target: PruneTarget,
+    /// Whether to only count the documents without deleting them.
+    pub dry_run: bool,
+}
+
+impl PruneOptions {
+    fn subcommand<'a, 'b>() -> ::clap::App<'a, 'b> {
+        let target = |name: &'b str, about: &'b str, value: Arg<'a, 'b>| {
+            SubCommand::with_name(name)
+                .about(about)
+                .arg(value.required(true))
+                .arg(Arg::with_name("dry_run").long("dry-run").help("Only counts the documents to delete"))
+        };
+        let value = |help: &'b str| Arg::with_name("value").help(help);
+        SubCommand::with_name("prune")
+            .about("Deletes the documents of a build, bundle, path or import source from the index")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(target("build", "Deletes all documents of a build", value("Build identifier, e.g. `14E304`")))
+            .subcommand(target("bundle", "Deletes all documents of a bundle (of the --build, if given)",
+                value("Bundle identifier, e.g. `com.apple.springboard`")))
+            .subcommand(target("path", "Deletes all documents of the bundles under a path (of the --build, if given)",
+                value("Path prefix of the bundles, e.g. `/Volumes/Erie14E304.D11D111OS/System/Library`")))
+            .subcommand(target("source", "Deletes all documents imported from a platform (of the --build, if given)",
+                value("Platform of the documents").possible_values(&["apple", "windows"])))
+    }
+
+    fn from_matches(matches: &ArgMatches) -> PruneOptions {
+        let (name, matches) = match matches.subcommand() {
+            (name, Some(m)) => (name, m),
+            _ => unreachable!("required by PruneOptions::subcommand"),
+        };
+        let value = matches.value_of("value").unwrap().to_owned();
+        PruneOptions {
+            target: match name {
+                "build" => PruneTarget::Build(value),
+                "bundle" => PruneTarget::Bundle(value),
+                "path" => PruneTarget::Path(value),
+                "source" => PruneTarget::Source(value.parse().unwrap()),
+                _ => unreachable!("rejected by PruneOptions::subcommand"),
+            },
+            dry_run: matches.is_present("dry_run"),
+        }
+    }
 }
 
 impl Options {
-    pub fn parse() -> Self {
-        let app = Self::clap().global_settings(&[
-            AppSettings::ArgRequiredElseHelp,
-            AppSettings::DeriveDisplayOrder,
-        ]);
-        Self::from_clap(app.get_matches())
+    pub fn parse() -> (Self, Command) {
+        let app = Self::clap()
+            .setting(AppSettings::ArgRequiredElseHelp)
+            .global_settings(&[AppSettings::DeriveDisplayOrder])
+            .subcommand(SubCommand::with_name("locales").about("Prints the effective locale alias table"))
+            .subcommand(DiffOptions::subcommand())
+            .subcommand(CheckOptions::subcommand())
+            .subcommand(CoverageOptions::subcommand())
+            .subcommand(ConsistencyOptions::subcommand())
+            .subcommand(LintOptions::subcommand())
+            .subcommand(SuggestOptions::subcommand())
+            .subcommand(PruneOptions::subcommand());
+        let matches = app.get_matches();
+
+        let command = match matches.subcommand() {
+            ("locales", _) => Command::Locales,
+            ("diff", Some(m)) => Command::Diff(DiffOptions::from_matches(m)),
+            ("check", Some(m)) => Command::Check(CheckOptions::from_matches(m)),
+            ("coverage", Some(m)) => Command::Coverage(CoverageOptions::from_matches(m)),
+            ("consistency", Some(m)) => Command::Consistency(ConsistencyOptions::from_matches(m)),
+            ("lint", Some(m)) => Command::Lint(LintOptions::from_matches(m)),
+            ("suggest", Some(m)) => Command::Suggest(SuggestOptions::from_matches(m)),
+            ("prune", Some(m)) => Command::Prune(PruneOptions::from_matches(m)),
+            _ => Command::Import,
+        };
+
+        let options = Self::from_clap(matches);
+        if command == Command::Import && options.root.is_none() {
+            Error::with_description("the root directory is not provided", ErrorKind::MissingRequiredArgument).exit();
+        }
+        (options, command)
     }
 }
 
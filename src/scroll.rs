@@ -0,0 +1,90 @@
+//! Reading every document matching a query with the scroll API.
+//!
+//! This is shared by the indexer and the search server, which send the requests with their own HTTP clients.
+
+use std::mem::replace;
+use serde_json::Value;
+
+/// How long Elasticsearch keeps the search context between two pages.
+const SCROLL_TIMEOUT: &str = "1m";
+
+/// Number of documents in each page.
+const SCROLL_SIZE: u32 = 1000;
+
+/// Reads every document of `index`/`type_` matching the query, and passes the `_source` of each to `f`.
+///
+/// The `post` function sends a JSON request to a path relative to the Elasticsearch URL, and returns the reply.
+pub fn scroll<E, P, F>(index: &str, type_: &str, query: Value, mut post: P, mut f: F) -> Result<(), E>
+    where P: FnMut(&str, &Value) -> Result<Value, E>, F: FnMut(Value)
+{
+    let mut path = format!("{}/{}/_search?scroll={}", index, type_, SCROLL_TIMEOUT);
+    let mut request = json!({
+        "query": query,
+        "size": SCROLL_SIZE,
+        "sort": ["_doc"],
+    });
+
+    loop {
+        let mut content = post(&path, &request)?;
+        let hits = match content["hits"]["hits"].as_array_mut() {
+            Some(hits) if !hits.is_empty() => replace(hits, Vec::new()),
+            _ => return Ok(()),
+        };
+        for mut hit in hits {
+            f(replace(&mut hit["_source"], Value::Null));
+        }
+
+        path = "_search/scroll".to_owned();
+        request = json!({
+            "scroll": SCROLL_TIMEOUT,
+            "scroll_id": content["_scroll_id"],
+        });
+    }
+}
+
+#[test]
+fn test_scroll() {
+    let mut requests = Vec::new();
+    let mut pages = vec![
+        json!({"_scroll_id": "a", "hits": {"hits": [{"_source": {"KEY": "1"}}, {"_source": {"KEY": "2"}}]}}),
+        json!({"_scroll_id": "b", "hits": {"hits": [{"_source": {"KEY": "3"}}]}}),
+        json!({"_scroll_id": "c", "hits": {"hits": []}}),
+    ].into_iter();
+    let mut documents = Vec::new();
+
+    let result: Result<(), ()> = scroll("localizations", "localization", json!({"match_all": {}}), |path, request| {
+        requests.push((path.to_owned(), request.clone()));
+        Ok(pages.next().unwrap())
+    }, |document| documents.push(document));
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(documents, vec![json!({"KEY": "1"}), json!({"KEY": "2"}), json!({"KEY": "3"})]);
+    assert_eq!(requests, vec![
+        ("localizations/localization/_search?scroll=1m".to_owned(), json!({
+            "query": {"match_all": {}},
+            "size": 1000,
+            "sort": ["_doc"],
+        })),
+        ("_search/scroll".to_owned(), json!({"scroll": "1m", "scroll_id": "a"})),
+        ("_search/scroll".to_owned(), json!({"scroll": "1m", "scroll_id": "b"})),
+    ]);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
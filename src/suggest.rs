@@ -0,0 +1,131 @@
+//! Suggest translations of new source texts from the index.
+//!
+//! The index is searched for entries whose source text matches (exactly or fuzzily) the text to translate, and their
+//! translations into the target locale are grouped. Like the search results of the web interface, each distinct
+//! translation is ranked by `score × count^0.25`, where `score` is the relevance of its best matching entry and `count`
+//! is the number of entries using it, so a translation used everywhere beats a slightly better matching one-off.
+
+use std::cmp::Ordering;
+use serde_json::Value;
+
+use lint::similarity;
+
+/// Number of distinct translations considered for each source text.
+const MAX_CANDIDATES: u32 = 10;
+
+/// Boost of entries with exactly the same source text over fuzzy matches.
+const EXACT_MATCH_BOOST: u32 = 10;
+
+/// A suggested translation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub text: String,
+    /// Confidence from 0 to 1, the share of matched entries using this translation scaled by the similarity of the
+    /// source texts.
+    pub confidence: f64,
+    /// The source text of the best matching entry using this translation.
+    pub matched_source: String,
+}
+
+/// Constructs the search request finding the translations of a source text.
+pub fn suggestion_query(source_locale: &str, source: &str, target_locale: &str, build: Option<&str>) -> Value {
+    let source_keyword = format!("{}.keyword", source_locale);
+    let target_keyword = format!("{}.keyword", target_locale);
+    let mut filters = vec![json!({"exists": {"field": target_keyword}})];
+    if let Some(build) = build {
+        filters.push(json!({"term": {"BUILD.keyword": build}}));
+    }
+
+    json!({
+        "size": 0,
+        "query": {
+            "bool": {
+                "must": {"match": {source_locale: {"query": source, "fuzziness": "AUTO"}}},
+                "should": {"term": {source_keyword: {"value": source, "boost": EXACT_MATCH_BOOST}}},
+                "filter": filters,
+            },
+        },
+        "aggregations": {
+            "translations": {
+                "terms": {"field": target_keyword, "size": MAX_CANDIDATES, "order": {"top_score": "desc"}},
+                "aggregations": {
+                    "top_score": {"max": {"script": {"source": "_score"}}},
+                    "top_hit": {"top_hits": {"size": 1, "_source": [source_locale]}},
+                },
+            },
+        },
+    })
+}
+
+/// Picks the best translation from the reply of a `suggestion_query`.
+pub fn best_suggestion(source_locale: &str, source: &str, reply: &Value) -> Option<Suggestion> {
+    let translations = &reply["aggregations"]["translations"];
+    let buckets = translations["buckets"].as_array()?;
+    let total = buckets.iter().filter_map(|b| b["doc_count"].as_u64()).sum::<u64>()
+        + translations["sum_other_doc_count"].as_u64().unwrap_or(0);
+
+    let rank = |bucket: &Value| {
+        let score = bucket["top_score"]["value"].as_f64().unwrap_or(0.0);
+        let count = bucket["doc_count"].as_f64().unwrap_or(0.0);
+        score * count.powf(0.25)
+    };
+    let best = buckets.iter().max_by(|a, b| rank(a).partial_cmp(&rank(b)).unwrap_or(Ordering::Equal))?;
+
+    let text = best["key"].as_str()?;
+    let matched_source = best["top_hit"]["hits"]["hits"][0]["_source"][source_locale].as_str().unwrap_or("");
+    let share = best["doc_count"].as_u64().unwrap_or(0) as f64 / total as f64;
+    Some(Suggestion {
+        text: text.to_owned(),
+        confidence: share * similarity(source, matched_source),
+        matched_source: matched_source.to_owned(),
+    })
+}
+
+#[test]
+fn test_best_suggestion() {
+    let bucket = |text: &str, count: u64, score: f64, source: &str| json!({
+        "key": text,
+        "doc_count": count,
+        "top_score": {"value": score},
+        "top_hit": {"hits": {"hits": [{"_source": {"en_US": source}}]}},
+    });
+    let reply = json!({
+        "aggregations": {
+            "translations": {
+                "sum_other_doc_count": 0,
+                "buckets": [
+                    bucket("Alle abbrechen", 1, 12.0, "Cancel All"),
+                    bucket("Abbrechen", 16, 9.0, "Cancel"),
+                    bucket("Abbruch", 3, 9.0, "Cancel"),
+                ],
+            },
+        },
+    });
+    assert_eq!(best_suggestion("en_US", "Cancel", &reply), Some(Suggestion {
+        text: "Abbrechen".to_owned(),
+        confidence: 0.8,
+        matched_source: "Cancel".to_owned(),
+    }));
+
+    let empty = json!({"aggregations": {"translations": {"sum_other_doc_count": 0, "buckets": []}}});
+    assert_eq!(best_suggestion("en_US", "Cancel", &empty), None);
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/
@@ -0,0 +1,442 @@
+//! Find and read Windows string resources.
+//!
+//! Three kinds of resource files are recognized:
+//!
+//! * `*.resx` — .NET XML resources. Localized variants carry the culture name before the extension, e.g.
+//!   `Strings.resx` (neutral) and `Strings.de-DE.resx`.
+//! * `*.resw` — UWP XML resources, with the same schema as `*.resx`. These are usually organized by culture-named
+//!   folders, e.g. `Strings/de-DE/Resources.resw`.
+//! * `*.mui` — Multilingual User Interface resource-only PE images, e.g. `System32/de-DE/shell32.dll.mui`. Only the
+//!   string table (`RT_STRING`) is read.
+//!
+//! If the root directory structure is like:
+//!
+//! ```text
+//! /root/
+//!     App/
+//!         Strings.resx
+//!         Strings.de-DE.resx
+//!     System32/
+//!         de-DE/shell32.dll.mui
+//!         fr-FR/shell32.dll.mui
+//! ```
+//!
+//! then running `scan_resource_sets("/root")` will return a hash map of
+//! `{"/root/App": [Strings.resx (neutral), Strings.resx (de-DE)], "/root/System32": [shell32.dll (de-DE), shell32.dll
+//! (fr-FR)]}`.
+
+use std::path::{Path, PathBuf};
+use std::ffi::OsStr;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use walkdir::{WalkDir, WalkDirIterator};
+use xml::reader::{EventReader, XmlEvent};
+
+use error::{Result, ErrorKind};
+use aliases::is_known_language;
+
+/// A single resource file of a resource set.
+pub struct ResourceFile {
+    /// Culture name of the resource, e.g. `de-DE`. `None` for neutral resources.
+    pub culture: Option<String>,
+    /// File name shared by all cultures of the resource set, e.g. `Strings.resx` or `shell32.dll`.
+    pub file: String,
+    /// Actual path of the resource file.
+    pub path: PathBuf,
+}
+
+/// A string resource read from a resource file.
+pub struct ResourceString {
+    pub name: String,
+    pub value: String,
+    pub comment: Option<String>,
+}
+
+/// Checks whether a string looks like a culture name, e.g. `de`, `de-DE`, `zh-Hans` or `sr-Latn-RS`. The language
+/// must be a known one, so folders like `bin` or `src` are not mistaken for cultures.
+fn is_culture_name(s: &str) -> bool {
+    let mut subtags = s.split('-');
+    let language = subtags.next().unwrap();
+    let is_subtag = |t: &str| 2 <= t.len() && t.len() <= 8 && t.bytes().all(|b| b.is_ascii_alphanumeric());
+    is_known_language(language) && subtags.all(is_subtag)
+}
+
+/// Classifies a resource file, returning its container directory and the resource file description.
+fn classify_resource_file(path: &Path) -> Option<(PathBuf, ResourceFile)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (stem, extension) = file_name.split_at(file_name.rfind('.')?);
+    let parent = path.parent()?;
+
+    // `de-DE/shell32.dll.mui` or `de-DE/Resources.resw`.
+    if let Some(dir_name) = parent.file_name().and_then(OsStr::to_str) {
+        if is_culture_name(dir_name) {
+            let file = if extension == ".mui" { stem } else { file_name };
+            return Some((parent.parent()?.to_owned(), ResourceFile {
+                culture: Some(dir_name.to_owned()),
+                file: file.to_owned(),
+                path: path.to_owned(),
+            }));
+        }
+    }
+
+    // `Strings.de-DE.resx` or `Strings.resx`.
+    let (file, culture) = match stem.rfind('.') {
+        Some(i) if is_culture_name(&stem[i+1 ..]) => (format!("{}{}", &stem[.. i], extension), Some(&stem[i+1 ..])),
+        _ => (file_name.to_owned(), None),
+    };
+    Some((parent.to_owned(), ResourceFile {
+        culture: culture.map(str::to_owned),
+        file: file,
+        path: path.to_owned(),
+    }))
+}
+
+#[test]
+fn test_classify_resource_file() {
+    let (dir, rf) = classify_resource_file(Path::new("/root/App/Strings.de-DE.resx")).unwrap();
+    assert_eq!(dir, Path::new("/root/App"));
+    assert_eq!(rf.culture.as_ref().map(|s| &**s), Some("de-DE"));
+    assert_eq!(rf.file, "Strings.resx");
+
+    let (dir, rf) = classify_resource_file(Path::new("/root/App/Strings.Designer.resx")).unwrap();
+    assert_eq!(dir, Path::new("/root/App"));
+    assert_eq!(rf.culture, None);
+    assert_eq!(rf.file, "Strings.Designer.resx");
+
+    let (dir, rf) = classify_resource_file(Path::new("/root/System32/zh-Hant/shell32.dll.mui")).unwrap();
+    assert_eq!(dir, Path::new("/root/System32"));
+    assert_eq!(rf.culture.as_ref().map(|s| &**s), Some("zh-Hant"));
+    assert_eq!(rf.file, "shell32.dll");
+
+    let (dir, rf) = classify_resource_file(Path::new("/root/Project/src/Strings.resx")).unwrap();
+    assert_eq!(dir, Path::new("/root/Project/src"));
+    assert_eq!(rf.culture, None);
+    assert_eq!(rf.file, "Strings.resx");
+
+    let (dir, rf) = classify_resource_file(Path::new("/root/Project/bin/Strings.fr.resx")).unwrap();
+    assert_eq!(dir, Path::new("/root/Project/bin"));
+    assert_eq!(rf.culture.as_ref().map(|s| &**s), Some("fr"));
+}
+
+/// Finds all Windows resource sets inside the given directory.
+pub fn scan_resource_sets<P: AsRef<Path>>(root: P) -> BTreeMap<PathBuf, Vec<ResourceFile>> {
+    let extensions = [Some(OsStr::new("resx")), Some(OsStr::new("resw")), Some(OsStr::new("mui"))];
+    let mut result = BTreeMap::new();
+
+    let mut walker = WalkDir::new(root).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => {
+                walker.skip_current_dir(); // probably "no permission" or something like that.
+                continue;
+            },
+        };
+        let path = entry.path();
+        if !entry.file_type().is_file() || !extensions.contains(&path.extension()) {
+            continue;
+        }
+        if let Some((container, resource_file)) = classify_resource_file(path) {
+            result.entry(container).or_insert_with(Vec::new).push(resource_file);
+        }
+    }
+
+    result
+}
+
+/// Reads all string resources from a `*.resx` or `*.resw` file.
+///
+/// Non-string resources (those with a `type` or `mimetype` attribute) are skipped.
+pub fn read_resx(path: &Path) -> Result<Vec<ResourceString>> {
+    parse_resx(BufReader::new(File::open(path)?))
+}
+
+fn parse_resx<R: Read>(input: R) -> Result<Vec<ResourceString>> {
+    #[derive(Clone, Copy)]
+    enum State { Outside, Data, Value, Comment }
+
+    let mut reader = EventReader::new(input);
+    let mut result = Vec::new();
+    let mut state = State::Outside;
+    let mut current = None;
+
+    loop {
+        match reader.next()? {
+            XmlEvent::StartElement { name, attributes, .. } => match (state, &*name.local_name) {
+                (State::Outside, "data") => {
+                    let is_string = attributes.iter().all(|a| match &*a.name.local_name {
+                        "type" | "mimetype" => false,
+                        _ => true,
+                    });
+                    let name = attributes.into_iter().find(|a| a.name.local_name == "name" && a.name.prefix.is_none());
+                    if let (true, Some(name)) = (is_string, name) {
+                        current = Some(ResourceString { name: name.value, value: String::new(), comment: None });
+                    }
+                    state = State::Data;
+                },
+                (State::Data, "value") => state = State::Value,
+                (State::Data, "comment") => state = State::Comment,
+                _ => {},
+            },
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => match (state, current.as_mut()) {
+                (State::Value, Some(rs)) => rs.value.push_str(&s),
+                (State::Comment, Some(rs)) => rs.comment.get_or_insert_with(String::new).push_str(&s),
+                _ => {},
+            },
+            XmlEvent::EndElement { name } => match (state, &*name.local_name) {
+                (State::Data, "data") => {
+                    result.extend(current.take());
+                    state = State::Outside;
+                },
+                (State::Value, "value") | (State::Comment, "comment") => state = State::Data,
+                _ => {},
+            },
+            XmlEvent::EndDocument => break,
+            _ => {},
+        }
+    }
+
+    Ok(result)
+}
+
+#[test]
+fn test_parse_resx() {
+    let resx = br#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <resheader name="resmimetype"><value>text/microsoft-resx</value></resheader>
+  <data name="Greeting" xml:space="preserve">
+    <value>Hello, &lt;b&gt;world&lt;/b&gt;!</value>
+    <comment>Shown on the start page</comment>
+  </data>
+  <data name="Empty" xml:space="preserve"><value /></data>
+  <data name="Icon" type="System.Drawing.Bitmap, System.Drawing"><value>AAAA</value></data>
+  <data name="Logo" mimetype="application/x-microsoft.net.object.binary.base64"><value>AAAA</value></data>
+</root>"#;
+    let strings = parse_resx(&resx[..]).unwrap();
+    assert_eq!(strings.len(), 2);
+    assert_eq!(strings[0].name, "Greeting");
+    assert_eq!(strings[0].value, "Hello, <b>world</b>!");
+    assert_eq!(strings[0].comment.as_ref().map(|s| &**s), Some("Shown on the start page"));
+    assert_eq!(strings[1].name, "Empty");
+    assert_eq!(strings[1].value, "");
+    assert_eq!(strings[1].comment, None);
+}
+
+/// Simple little-endian reader over a PE image.
+struct PeImage(Vec<u8>);
+
+impl PeImage {
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let b = self.0.get(offset .. offset + 2)?;
+        Some(u16::from(b[0]) | u16::from(b[1]) << 8)
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        Some(u32::from(self.u16_at(offset)?) | u32::from(self.u16_at(offset + 2)?) << 16)
+    }
+
+    /// Finds the file offset and the RVA of the resource section.
+    fn resource_section(&self) -> Option<(usize, u32)> {
+        if self.0.get(.. 2) != Some(b"MZ") {
+            return None;
+        }
+        let pe = self.u32_at(0x3c)? as usize;
+        if self.0.get(pe .. pe + 4) != Some(b"PE\0\0") {
+            return None;
+        }
+        let section_count = self.u16_at(pe + 6)? as usize;
+        let optional_header = pe + 24;
+        let optional_header_size = self.u16_at(pe + 20)? as usize;
+        let data_directories = match self.u16_at(optional_header)? {
+            0x10b => optional_header + 96,
+            0x20b => optional_header + 112,
+            _ => return None,
+        };
+        let resource_rva = self.u32_at(data_directories + 2*8)?;
+
+        let sections = optional_header + optional_header_size;
+        for section in (0 .. section_count).map(|i| sections + i*40) {
+            let virtual_size = self.u32_at(section + 8)?;
+            let virtual_address = self.u32_at(section + 12)?;
+            let raw_offset = self.u32_at(section + 20)?;
+            let end = virtual_address.checked_add(virtual_size)?;
+            if virtual_address <= resource_rva && resource_rva < end {
+                return Some((raw_offset as usize + (resource_rva - virtual_address) as usize, resource_rva));
+            }
+        }
+        None
+    }
+
+    /// Lists the `(id, offset, is_directory)` entries of a resource directory. Named entries are skipped.
+    fn resource_directory(&self, base: usize, offset: usize) -> Vec<(u32, usize, bool)> {
+        let dir = base + offset;
+        let named_count = self.u16_at(dir + 12).unwrap_or(0) as usize;
+        let id_count = self.u16_at(dir + 14).unwrap_or(0) as usize;
+        (named_count .. named_count + id_count).filter_map(|i| {
+            let entry = dir + 16 + i*8;
+            let id = self.u32_at(entry)?;
+            let target = self.u32_at(entry + 4)?;
+            Some((id, (target & 0x7fff_ffff) as usize, target & 0x8000_0000 != 0))
+        }).collect()
+    }
+
+    /// Reads all strings in the `RT_STRING` resources.
+    fn string_table(&self) -> Result<Vec<(u32, String)>> {
+        const RT_STRING: u32 = 6;
+        // String IDs are 16-bit, so there are at most 4096 blocks of 16 strings, numbered from 1.
+        const MAX_BLOCK_ID: u32 = 0x1000;
+
+        let mut result = Vec::new();
+        let (base, base_rva) = match self.resource_section() {
+            Some(s) => s,
+            None => bail!(ErrorKind::InvalidMui),
+        };
+
+        let types = self.resource_directory(base, 0);
+        for (_, blocks, _) in types.into_iter().filter(|&(id, _, is_dir)| id == RT_STRING && is_dir) {
+            for (block_id, languages, _) in self.resource_directory(base, blocks).into_iter().filter(|e| e.2) {
+                let first_id = match block_id.checked_sub(1) {
+                    Some(index) if block_id <= MAX_BLOCK_ID => index * 16,
+                    _ => bail!(ErrorKind::InvalidMui),
+                };
+                // All languages in a *.mui file are the same, so just pick the first one.
+                let data_entry = match self.resource_directory(base, languages).into_iter().find(|e| !e.2) {
+                    Some((_, offset, _)) => base + offset,
+                    None => continue,
+                };
+                let (data_rva, data_size) = match (self.u32_at(data_entry), self.u32_at(data_entry + 4)) {
+                    (Some(rva), Some(size)) if rva >= base_rva => (rva, size as usize),
+                    _ => continue,
+                };
+                let start = base + (data_rva - base_rva) as usize;
+                let end = match start.checked_add(data_size) {
+                    Some(end) if end <= self.0.len() => end,
+                    _ => bail!(ErrorKind::InvalidMui),
+                };
+
+                // Each block contains 16 length-prefixed UTF-16 strings.
+                let mut offset = start;
+                for index in 0 .. 16 {
+                    let len = match self.u16_at(offset) {
+                        Some(len) if offset + 2 + len as usize * 2 <= end => len as usize,
+                        _ => break,
+                    };
+                    if len > 0 {
+                        let units = (0 .. len).map(|j| self.u16_at(offset + 2 + j*2)).collect::<Option<Vec<_>>>();
+                        match units {
+                            Some(units) => result.push((first_id + index, String::from_utf16_lossy(&units))),
+                            None => bail!(ErrorKind::InvalidMui),
+                        }
+                    }
+                    offset += 2 + len * 2;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[test]
+fn test_string_table() {
+    fn put_u16(image: &mut [u8], offset: usize, value: u16) {
+        image[offset] = value as u8;
+        image[offset + 1] = (value >> 8) as u8;
+    }
+
+    fn put_u32(image: &mut [u8], offset: usize, value: u32) {
+        put_u16(image, offset, value as u16);
+        put_u16(image, offset + 2, (value >> 16) as u16);
+    }
+
+    /// Builds a minimal PE32 image with one section holding a string table block, with the strings "OK" and "Cancel" at
+    /// the indices 0 and 2.
+    fn mui_image(block_id: u32, virtual_size: u32) -> PeImage {
+        let mut image = vec![0; 0x300];
+        image[.. 2].copy_from_slice(b"MZ");
+        put_u32(&mut image, 0x3c, 0x40);
+        image[0x40 .. 0x44].copy_from_slice(b"PE\0\0");
+        put_u16(&mut image, 0x46, 1); // number of sections
+        put_u16(&mut image, 0x54, 0xe0); // size of optional header
+        put_u16(&mut image, 0x58, 0x10b); // PE32 magic
+        put_u32(&mut image, 0xc8, 0x1000); // RVA of the resource directory
+        put_u32(&mut image, 0x138 + 8, virtual_size);
+        put_u32(&mut image, 0x138 + 12, 0x1000);
+        put_u32(&mut image, 0x138 + 20, 0x200);
+
+        // type directory -> block directory -> language directory -> data entry.
+        put_u16(&mut image, 0x200 + 14, 1);
+        put_u32(&mut image, 0x200 + 16, 6);
+        put_u32(&mut image, 0x200 + 20, 0x8000_0018);
+        put_u16(&mut image, 0x218 + 14, 1);
+        put_u32(&mut image, 0x218 + 16, block_id);
+        put_u32(&mut image, 0x218 + 20, 0x8000_0030);
+        put_u16(&mut image, 0x230 + 14, 1);
+        put_u32(&mut image, 0x230 + 16, 0x407);
+        put_u32(&mut image, 0x230 + 20, 0x48);
+        put_u32(&mut image, 0x248, 0x1058);
+        put_u32(&mut image, 0x248 + 4, 48);
+
+        let mut offset = 0x258;
+        for s in &["OK", "", "Cancel", "", "", "", "", "", "", "", "", "", "", "", "", ""] {
+            put_u16(&mut image, offset, s.len() as u16);
+            for (i, c) in s.encode_utf16().enumerate() {
+                put_u16(&mut image, offset + 2 + i*2, c);
+            }
+            offset += 2 + s.len() * 2;
+        }
+        PeImage(image)
+    }
+
+    let table = mui_image(3, 0x100).string_table().unwrap();
+    assert_eq!(table, vec![(32, "OK".to_owned()), (34, "Cancel".to_owned())]);
+
+    assert!(mui_image(0, 0x100).string_table().is_err());
+    assert!(mui_image(0x10000, 0x100).string_table().is_err());
+    assert!(mui_image(3, 0xffff_f000).string_table().is_err());
+
+    // the data entry claims more bytes than the truncated file has.
+    let mut truncated = mui_image(3, 0x100);
+    truncated.0.truncate(0x260);
+    assert!(truncated.string_table().is_err());
+}
+
+/// Reads all strings from the string table of a `*.mui` file. The names are the decimal string IDs.
+pub fn read_mui(path: &Path) -> Result<Vec<ResourceString>> {
+    let mut content = Vec::new();
+    File::open(path)?.read_to_end(&mut content)?;
+    Ok(PeImage(content).string_table()?.into_iter().map(|(id, value)| ResourceString {
+        name: id.to_string(),
+        value: value,
+        comment: None,
+    }).collect())
+}
+
+/// Reads all strings from a resource file.
+pub fn read_resource_file(path: &Path) -> Result<Vec<ResourceString>> {
+    if path.extension() == Some(OsStr::new("mui")) {
+        read_mui(path)
+    } else {
+        read_resx(path)
+    }
+}
+
+/*
+
+Copyright 2017 kennytm
+
+Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated
+documentation files (the "Software"), to deal in the Software without restriction, including without limitation the
+rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit
+persons to whom the Software is furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all copies or substantial portions of the
+Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE
+WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR
+OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+*/